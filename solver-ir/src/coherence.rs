@@ -0,0 +1,211 @@
+//! Coherence checking.
+//!
+//! Impls of the same trait must be disjoint, otherwise the solver can't choose between them.
+//! This module finds every pair of overlapping impls and explains where they overlap.
+
+use crate::{
+    interner::{ImplId, Interner, TraitId},
+    patterns::{ExactPatternSeq, PatternElement, PatternError},
+    primitives::{ConstValue, TypeId},
+};
+
+/// Pair of impls of the same trait that apply to the same types.
+#[derive(Debug)]
+pub struct ImplOverlap<I: Interner> {
+    pub trait_id: TraitId<I>,
+    pub impls: [ImplId<I>; 2],
+    /// Intersection of headers of both impls, see `ExactPatternSeq::intersection`. It may still
+    /// contain placeholders, in which case both impls apply to every instance of it.
+    pub intersection: Box<ExactPatternSeq<I>>,
+    /// Instance of `intersection` without placeholders, i.e. an example of concrete types that
+    /// both impls apply to.
+    pub witness: Box<ExactPatternSeq<I>>,
+}
+
+impl<I: Interner> ImplOverlap<I> {
+    /// Writes human readable description of the overlap.
+    ///
+    /// Fails if a header of the impls doesn't have the layout of a trait impl pattern, see
+    /// `PatternSeq::format_as_trait_impl`.
+    pub fn format(&self, interner: I, f: &mut dyn std::fmt::Write) -> Result<(), PatternError> {
+        let trait_data = interner.get_trait_by_id(self.trait_id);
+        writeln!(
+            f,
+            "conflicting impls of trait `{}`:",
+            interner.trait_data(&trait_data).name
+        )?;
+        for impl_id in self.impls {
+            let impl_data = interner.get_impl_by_id(impl_id);
+            write!(f, "    ")?;
+            interner
+                .impl_data(&impl_data)
+                .pattern
+                .format_as_trait_impl(interner, self.trait_id, f)?;
+            writeln!(f)?;
+        }
+        write!(f, "overlapping headers: ")?;
+        self.intersection
+            .format_as_trait_impl(interner, self.trait_id, f)?;
+        write!(f, "\nexample: ")?;
+        self.witness
+            .format_as_trait_impl(interner, self.trait_id, f)
+    }
+}
+
+/// Returns overlaps between all pairs of impls of `trait_id`.
+pub fn trait_overlaps<I: Interner>(interner: I, trait_id: TraitId<I>) -> Vec<ImplOverlap<I>> {
+    let impls: Vec<_> = interner
+        .trait_impls(trait_id)
+        .into_iter()
+        .map(|impl_id| (impl_id, interner.get_impl_by_id(impl_id)))
+        .collect();
+    let mut overlaps = Vec::new();
+    for (i, (first_id, first)) in impls.iter().enumerate() {
+        for (second_id, second) in &impls[i + 1..] {
+            let first = &interner.impl_data(first).pattern;
+            let second = &interner.impl_data(second).pattern;
+            let Some(intersection) = first.intersection(second) else {
+                continue;
+            };
+            overlaps.push(ImplOverlap {
+                trait_id,
+                impls: [*first_id, *second_id],
+                witness: instantiate_placeholders(&intersection),
+                intersection,
+            });
+        }
+    }
+    overlaps
+}
+
+/// Returns instance of `pattern` with every type placeholder replaced by `()` and every const
+/// placeholder replaced by `0`.
+fn instantiate_placeholders<I: Interner>(pattern: &ExactPatternSeq<I>) -> Box<ExactPatternSeq<I>> {
+    let instance: Vec<_> = pattern
+        .iter()
+        .map(|&elem| match elem {
+            PatternElement::TypePlaceholder(_) => PatternElement::TypeConstructor {
+                args_length: 0,
+                type_id: TypeId::Tuple(0),
+            },
+            PatternElement::ConstPlaceholder(_) => PatternElement::ConstValue(ConstValue(0)),
+            elem => elem,
+        })
+        .collect();
+    // Safe because placeholders are replaced by single element patterns of the same kind
+    unsafe { ExactPatternSeq::new_unchecked(&instance) }.boxed()
+}
+
+/// Returns overlaps between impls of every trait known to the interner.
+pub fn check_coherence<I: Interner>(interner: I) -> Vec<ImplOverlap<I>> {
+    interner
+        .traits()
+        .into_iter()
+        .flat_map(|trait_id| trait_overlaps(interner, trait_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, test_interner::TestInterner};
+    use solver_macros::add_impls;
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn overlapping_impls_are_reported_once_per_pair() {
+        let interner = TestInterner::new();
+        let (A, B, Clone, Copy) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait Clone;
+            trait Copy;
+        });
+        let (b_t, b_a, _, t_a, a_t, _) = add_impls!(interner, {
+            impl<T> B<T> as Clone;
+            impl B<A> as Clone;
+            impl A as Clone;
+            impl<T> (T, A) as Clone;
+            impl<T> (A, T) as Clone;
+            impl<T> (T, A) as Copy;
+        });
+        let overlaps = check_coherence(interner);
+        let reports: Vec<_> = overlaps
+            .iter()
+            .map(|overlap| {
+                assert_eq!(overlap.trait_id, Clone);
+                let mut report = String::new();
+                overlap.format(interner, &mut report).unwrap();
+                (overlap.impls, report)
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            [
+                (
+                    [b_t, b_a],
+                    "conflicting impls of trait `Clone`:\n    \
+                     impl<T0> B<T0> as Clone\n    \
+                     impl B<A> as Clone\n\
+                     overlapping headers: impl B<A> as Clone\n\
+                     example: impl B<A> as Clone"
+                        .to_string()
+                ),
+                (
+                    [t_a, a_t],
+                    "conflicting impls of trait `Clone`:\n    \
+                     impl<T0> (T0, A) as Clone\n    \
+                     impl<T0> (A, T0) as Clone\n\
+                     overlapping headers: impl (A, A) as Clone\n\
+                     example: impl (A, A) as Clone"
+                        .to_string()
+                ),
+            ]
+        );
+        assert!(trait_overlaps(interner, Copy).is_empty());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn witnesses_instantiate_placeholders_of_intersections() {
+        let interner = TestInterner::new();
+        let (A, B, PartialEq, Debug) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Debug;
+        });
+        add_impls!(interner, {
+            impl<T, U> B<T> as PartialEq<U>;
+            impl<T> T as PartialEq<T>;
+            impl<const N: usize> [A; N] as Debug;
+            impl<T, const N: usize> [T; N] as Debug;
+        });
+        let reports: Vec<_> = check_coherence(interner)
+            .iter()
+            .map(|overlap| {
+                let repr = |pattern: &ExactPatternSeq<_>| {
+                    let mut repr = String::new();
+                    pattern
+                        .format_as_trait_impl(interner, overlap.trait_id, &mut repr)
+                        .unwrap();
+                    repr
+                };
+                (repr(&overlap.intersection), repr(&overlap.witness))
+            })
+            .collect();
+        assert_eq!(
+            reports,
+            [
+                (
+                    "impl<T0> B<T0> as PartialEq<B<T0>>".to_string(),
+                    "impl B<()> as PartialEq<B<()>>".to_string()
+                ),
+                (
+                    "impl<const N0> [A; N0] as Debug".to_string(),
+                    "impl [A; 0] as Debug".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Discrimination tree over patterns.
+//!
+//! `PatternSeq` is a prefix-order encoding, so every pattern is a path of keys from the root of a
+//! trie: type constructors and const values are keys of their own, while placeholders and
+//! inference variables become a wildcard key. Looking up a query walks only the branches that can
+//! unify with it, so candidates for a goal are found without unifying the goal with every stored
+//! pattern.
+//!
+//! The tree over-approximates: repeated placeholders are not checked to be equal, so every
+//! returned candidate still has to be unified with the query.
+
+use crate::{
+    interner::Interner,
+    patterns::{PatternElement, PatternSeq},
+    primitives::{ConstValue, TypeId},
+};
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+/// Key of an edge of a `DiscriminationTree`.
+#[derive(Clone, Copy, Debug)]
+enum Key<I: Interner> {
+    /// Type constructor with the number of its direct arguments.
+    Constructor {
+        type_id: TypeId<I>,
+        arity: usize,
+    },
+    Const(ConstValue),
+    /// Placeholder or inference variable, which may stand for any type or const.
+    Wildcard,
+}
+
+impl<I: Interner> PartialEq for Key<I> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Constructor {
+                    type_id: l_type_id,
+                    arity: l_arity,
+                },
+                Self::Constructor {
+                    type_id: r_type_id,
+                    arity: r_arity,
+                },
+            ) => l_type_id == r_type_id && l_arity == r_arity,
+            (Self::Const(l0), Self::Const(r0)) => l0 == r0,
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+
+impl<I: Interner> Eq for Key<I> {}
+
+impl<I: Interner> Hash for Key<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Constructor { type_id, arity } => {
+                type_id.hash(state);
+                arity.hash(state);
+            }
+            Self::Const(value) => value.hash(state),
+            Self::Wildcard => {}
+        }
+    }
+}
+
+impl<I: Interner> Key<I> {
+    /// Returns the number of patterns that follow this key as its arguments.
+    fn arity(self) -> usize {
+        match self {
+            Key::Constructor { arity, .. } => arity,
+            Key::Const(_) | Key::Wildcard => 0,
+        }
+    }
+}
+
+/// Returns the number of elements of the pattern starting with `first`.
+fn pattern_len<I: Interner>(first: &PatternElement<I>) -> usize {
+    match *first {
+        PatternElement::TypeConstructor { args_length, .. } => 1 + args_length,
+        PatternElement::TypePlaceholder(_)
+        | PatternElement::InferredType(_)
+        | PatternElement::ConstValue(_)
+        | PatternElement::ConstPlaceholder(_)
+        | PatternElement::InferredConst(_) => 1,
+    }
+}
+
+#[derive(Debug)]
+struct Node<I: Interner, V> {
+    /// Children under type constructors and const values, which are looked up by the key of the
+    /// query instead of being scanned.
+    children: HashMap<Key<I>, Node<I, V>>,
+    /// Child under `Key::Wildcard`, which may unify with any query.
+    wildcard: Option<Box<Node<I, V>>>,
+    /// Values of patterns ending at this node, together with their insertion order.
+    values: Vec<(usize, V)>,
+}
+
+impl<I: Interner, V> Default for Node<I, V> {
+    fn default() -> Self {
+        Self {
+            children: HashMap::new(),
+            wildcard: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<I: Interner, V> Node<I, V> {
+    fn child_mut(&mut self, key: Key<I>) -> &mut Self {
+        match key {
+            Key::Wildcard => self.wildcard.get_or_insert_default(),
+            Key::Constructor { .. } | Key::Const(_) => self.children.entry(key).or_default(),
+        }
+    }
+
+    /// Collects values of stored patterns that may unify with `query`.
+    fn collect<'a>(&'a self, query: &[PatternElement<I>], out: &mut Vec<&'a (usize, V)>) {
+        let Some(first) = query.first() else {
+            out.extend(self.values.iter());
+            return;
+        };
+        let first_len = pattern_len(first);
+        // Stored wildcard covers the whole first pattern of the query
+        if let Some(wildcard) = &self.wildcard {
+            wildcard.collect(&query[first_len..], out);
+        }
+        match *first {
+            // Inference variable of the query covers the whole stored pattern
+            PatternElement::InferredType(_) | PatternElement::InferredConst(_) => {
+                for (key, child) in &self.children {
+                    let mut ends = Vec::new();
+                    child.skip(key.arity(), &mut ends);
+                    for end in ends {
+                        end.collect(&query[1..], out);
+                    }
+                }
+            }
+            PatternElement::TypeConstructor { type_id, .. } => {
+                let mut arity = 0;
+                let mut args = &query[1..first_len];
+                while let Some(arg) = args.first() {
+                    args = &args[pattern_len(arg)..];
+                    arity += 1;
+                }
+                if let Some(child) = self.children.get(&Key::Constructor { type_id, arity }) {
+                    child.collect(&query[1..], out);
+                }
+            }
+            PatternElement::ConstValue(value) => {
+                if let Some(child) = self.children.get(&Key::Const(value)) {
+                    child.collect(&query[1..], out);
+                }
+            }
+            // Placeholders of the query are rigid, so they match only stored wildcards
+            PatternElement::TypePlaceholder(_) | PatternElement::ConstPlaceholder(_) => {}
+        }
+    }
+
+    /// Collects nodes reached after skipping `pending` whole patterns.
+    fn skip<'a>(&'a self, pending: usize, out: &mut Vec<&'a Self>) {
+        if pending == 0 {
+            out.push(self);
+            return;
+        }
+        for (key, child) in &self.children {
+            child.skip(pending - 1 + key.arity(), out);
+        }
+        if let Some(wildcard) = &self.wildcard {
+            wildcard.skip(pending - 1, out);
+        }
+    }
+}
+
+/// Index from patterns to values, e.g. from impl headers to impls.
+#[derive(Debug)]
+pub struct DiscriminationTree<I: Interner, V> {
+    root: Node<I, V>,
+    len: usize,
+}
+
+impl<I: Interner, V> Default for DiscriminationTree<I, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Interner, V> DiscriminationTree<I, V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of stored values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the tree stores no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Stores `value` under `pattern`.
+    ///
+    /// Placeholders and inference variables of `pattern` match anything, e.g. placeholders of an
+    /// impl header.
+    pub fn insert(&mut self, pattern: &PatternSeq<I>, value: V) {
+        let mut keys = Vec::with_capacity(pattern.len());
+        Self::keys_of(pattern, &mut keys);
+        let node = keys
+            .into_iter()
+            .fold(&mut self.root, |node, key| node.child_mut(key));
+        node.values.push((self.len, value));
+        self.len += 1;
+    }
+
+    fn keys_of(pattern: &PatternSeq<I>, out: &mut Vec<Key<I>>) {
+        for pat in pattern {
+            match *pat.first() {
+                PatternElement::TypeConstructor { type_id, .. } => {
+                    let args = pat.args();
+                    out.push(Key::Constructor {
+                        type_id,
+                        arity: args.map_or(0, |args| args.into_iter().count()),
+                    });
+                    if let Some(args) = args {
+                        Self::keys_of(args, out);
+                    }
+                }
+                PatternElement::ConstValue(value) => out.push(Key::Const(value)),
+                PatternElement::TypePlaceholder(_)
+                | PatternElement::InferredType(_)
+                | PatternElement::ConstPlaceholder(_)
+                | PatternElement::InferredConst(_) => out.push(Key::Wildcard),
+            }
+        }
+    }
+
+    /// Returns values of stored patterns that may unify with `query`, in insertion order.
+    ///
+    /// Inference variables of `query` match anything, while its placeholders are rigid and match
+    /// only placeholders and inference variables of stored patterns.
+    pub fn candidates(&self, query: &PatternSeq<I>) -> Vec<&V> {
+        let mut found = Vec::new();
+        self.root.collect(query, &mut found);
+        found.sort_by_key(|(order, _)| *order);
+        found.into_iter().map(|(_, value)| value).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, patterns::ExactPatternSeq, test_interner::TestInterner};
+
+    type Interner = &'static TestInterner;
+
+    /// Creates interner with ADTs `A`, `B<T>` and `C`, and tree storing index of every pattern.
+    #[allow(non_snake_case, clippy::type_complexity)]
+    fn tree_of(
+        stored: &[&str],
+    ) -> (
+        Interner,
+        Vec<Box<ExactPatternSeq<Interner>>>,
+        DiscriminationTree<Interner, usize>,
+    ) {
+        let interner = TestInterner::new();
+        add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct C;
+        });
+        let stored: Vec<_> = stored
+            .iter()
+            .map(|text| {
+                let pattern = PatternSeq::parse(interner, text).unwrap();
+                ExactPatternSeq::new(&pattern).unwrap().boxed()
+            })
+            .collect();
+        let mut tree = DiscriminationTree::new();
+        for (idx, pattern) in stored.iter().enumerate() {
+            tree.insert(pattern, idx);
+        }
+        (interner, stored, tree)
+    }
+
+    #[test]
+    fn candidates_are_the_matching_stored_patterns() {
+        let (interner, stored, tree) = tree_of(&[
+            "A", "B<T0>", "B<A>", "B<B<T0>>", "(T0, A)", "(T0, T1)", "[A; 3]", "[T0; N1]", "&T0",
+            "T0",
+        ]);
+        assert_eq!(tree.len(), stored.len());
+        for query in [
+            "A",
+            "C",
+            "B<?>",
+            "B<C>",
+            "B<B<A>>",
+            "(C, A)",
+            "(A, C)",
+            "?",
+            "[A; 3]",
+            "[A; 4]",
+            "[?; ?]",
+            "T0",
+            "B<T0>",
+            "&A",
+            "(?, ?)",
+            "(A, C, A)",
+        ] {
+            let query = PatternSeq::parse(interner, query).unwrap();
+            let linear_scan: Vec<_> = (0..stored.len())
+                .filter(|&idx| query.matches(&stored[idx]))
+                .collect();
+            let candidates: Vec<_> = tree.candidates(&query).into_iter().copied().collect();
+            assert_eq!(candidates, linear_scan, "query {:?}", query);
+        }
+    }
+
+    #[test]
+    fn candidates_ignore_repeated_placeholders() {
+        let (interner, stored, tree) = tree_of(&["(T0, T0)", "(T0, T1)", "(A, T0)"]);
+        let query = PatternSeq::parse(interner, "(A, C)").unwrap();
+        assert!(!query.matches(&stored[0]));
+        assert_eq!(tree.candidates(&query), [&0, &1, &2]);
+        let query = PatternSeq::parse(interner, "(C, C)").unwrap();
+        assert_eq!(tree.candidates(&query), [&0, &1]);
+    }
+}
@@ -0,0 +1,134 @@
+//! Elaboration of trait bounds.
+//!
+//! Bound `Type: Trait<Args>` implies every supertrait bound of `Trait` with `Self` replaced by
+//! `Type` and generic parameters of `Trait` replaced by `Args`. Elaboration expands a set of bounds
+//! into the transitive closure of the bounds they imply, so e.g. `T: Ord` also yields `T: Eq`.
+
+use crate::{
+    interner::{Interner, TraitId},
+    patterns::{ExactPatternSeq, Pattern, PatternElement, PatternSeq},
+    primitives::WhereClause,
+};
+
+/// Returns `clauses` together with every bound implied by them through supertraits.
+///
+/// Every bound is returned only once, so cyclic supertraits don't prevent elaboration from
+/// terminating.
+pub fn elaborate<I: Interner>(interner: I, clauses: &[WhereClause<I>]) -> Vec<WhereClause<I>> {
+    let bounds = clauses
+        .iter()
+        .map(|clause| (clause.trait_id, PatternSeq::boxed(&clause.pattern)))
+        .collect();
+    elaborate_patterns(interner, bounds)
+        .into_iter()
+        .map(|(trait_id, pattern)| WhereClause {
+            trait_id,
+            // Safe because substituting placeholders of supertraits with exact patterns yields
+            // an exact pattern
+            pattern: unsafe { ExactPatternSeq::new_unchecked(&pattern) }.boxed(),
+        })
+        .collect()
+}
+
+/// Same as `elaborate`, but for bounds whose patterns may contain inference variables.
+///
+/// `bounds` have the same layout as `TraitGoal`s: the bounded type followed by generic arguments
+/// of the trait.
+pub(crate) fn elaborate_patterns<I: Interner>(
+    interner: I,
+    bounds: Vec<(TraitId<I>, Box<PatternSeq<I>>)>,
+) -> Vec<(TraitId<I>, Box<PatternSeq<I>>)> {
+    let mut elaborated: Vec<(TraitId<I>, Box<PatternSeq<I>>)> = Vec::new();
+    let mut stack = bounds;
+    stack.reverse();
+    while let Some((trait_id, pattern)) = stack.pop() {
+        if elaborated
+            .iter()
+            .any(|(other_id, other)| *other_id == trait_id && *other == pattern)
+        {
+            continue;
+        }
+        let trait_data = interner.get_trait_by_id(trait_id);
+        let values: Vec<_> = pattern.into_iter().collect();
+        // Supertraits are pushed in reverse so they are elaborated in declaration order
+        for supertrait in interner.trait_data(&trait_data).supertraits.iter().rev() {
+            let mut substituted = Vec::with_capacity(supertrait.pattern.len());
+            substitute_into(&supertrait.pattern, &values, &mut substituted);
+            // Safe because placeholders are replaced by patterns of the same kind
+            let substituted = unsafe { PatternSeq::new_boxed_unchecked(substituted.into()) };
+            stack.push((supertrait.trait_id, substituted));
+        }
+        elaborated.push((trait_id, pattern));
+    }
+    elaborated
+}
+
+/// Writes `pattern` with every placeholder `idx` replaced by `values[idx.index()]` to `out`.
+///
+/// Placeholders of supertraits are `Self` and generic parameters of the trait, which interners
+/// check with `WhereClause::check_trait_bound`, so they are always in bounds of `values`.
+fn substitute_into<I: Interner>(
+    pattern: &PatternSeq<I>,
+    values: &[&Pattern<I>],
+    out: &mut Vec<PatternElement<I>>,
+) {
+    for pat in pattern {
+        match *pat.first() {
+            PatternElement::TypeConstructor { type_id, .. } => {
+                let start = out.len();
+                out.push(PatternElement::TypeConstructor {
+                    args_length: 0,
+                    type_id,
+                });
+                if let Some(args) = pat.args() {
+                    substitute_into(args, values, out);
+                }
+                out[start] = PatternElement::TypeConstructor {
+                    args_length: out.len() - start - 1,
+                    type_id,
+                };
+            }
+            elem => match elem.placeholder() {
+                Some(idx) => out.extend(values[idx.index()].iter()),
+                None => out.push(elem),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, test_interner::TestInterner};
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn bounds_imply_supertraits_with_substituted_arguments() {
+        let interner = TestInterner::new();
+        let (_A, _B, _PartialEq, _Eq, _Ord) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Eq<T>: PartialEq<T> + PartialEq<B<T>>;
+            trait Ord: Eq<Self>;
+        });
+        let clause = WhereClause::parse(interner, "impl<T> B<T> as Ord").unwrap();
+        let elaborated: Vec<_> = elaborate(interner, &[clause])
+            .iter()
+            .map(|clause| {
+                let mut repr = String::new();
+                clause.format(interner, &mut repr).unwrap();
+                repr
+            })
+            .collect();
+        assert_eq!(
+            elaborated,
+            [
+                "B<T0>: Ord",
+                "B<T0>: Eq<B<T0>>",
+                "B<T0>: PartialEq<B<T0>>",
+                "B<T0>: PartialEq<B<B<T0>>>",
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Rewriting of interned types.
+//!
+//! `TypeFolder` maps every type and const reachable from a value, and the value is rebuilt from
+//! the results in the interner of the folder. Overriding `fold_type` replaces a type; calling
+//! `Type::super_fold_with` from it rebuilds the type from its folded components instead. Folders
+//! stop early by returning an error.
+
+use crate::{
+    interner::{GenericArg, Interner, Substitution, Type},
+    primitives::{ConstValue, GenericArgData, TypeKind},
+};
+
+pub trait TypeFolder<I: Interner> {
+    /// Error that stops folding early.
+    type Error;
+
+    fn interner(&self) -> I;
+
+    /// Folds `ty`, by default by folding its components.
+    fn fold_type(&mut self, ty: &Type<I>) -> Result<Type<I>, Self::Error> {
+        ty.super_fold_with(self)
+    }
+
+    /// Folds const argument, e.g. the length of an array.
+    fn fold_const(&mut self, value: ConstValue) -> Result<ConstValue, Self::Error> {
+        Ok(value)
+    }
+}
+
+/// Values that contain types which can be rewritten by a `TypeFolder`.
+pub trait TypeFoldable<I: Interner>: Sized {
+    fn fold_with<F: TypeFolder<I> + ?Sized>(&self, folder: &mut F) -> Result<Self, F::Error>;
+}
+
+impl<I: Interner> Type<I> {
+    /// Rebuilds `self` from its folded components, without folding `self` itself.
+    pub fn super_fold_with<F: TypeFolder<I> + ?Sized>(
+        &self,
+        folder: &mut F,
+    ) -> Result<Self, F::Error> {
+        let interner = folder.interner();
+        let ty_kind = match self.kind(interner) {
+            &TypeKind::Adt(adt_id, ref subst) => TypeKind::Adt(adt_id, subst.fold_with(folder)?),
+            &TypeKind::Scalar(scalar) => TypeKind::Scalar(scalar),
+            TypeKind::NonZero(ty) => TypeKind::NonZero(ty.fold_with(folder)?),
+            TypeKind::Slice(ty) => TypeKind::Slice(ty.fold_with(folder)?),
+            &TypeKind::Array(ref ty, len) => {
+                TypeKind::Array(ty.fold_with(folder)?, folder.fold_const(len)?)
+            }
+            TypeKind::Tuple(subst) => TypeKind::Tuple(subst.fold_with(folder)?),
+            &TypeKind::Ref(qual, ref ty) => TypeKind::Ref(qual, ty.fold_with(folder)?),
+            &TypeKind::Ptr(qual, ref ty) => TypeKind::Ptr(qual, ty.fold_with(folder)?),
+            TypeKind::Never => TypeKind::Never,
+            TypeKind::FnPtr(subst) => TypeKind::FnPtr(subst.fold_with(folder)?),
+            &TypeKind::Dyn(trait_id, ref subst) => {
+                TypeKind::Dyn(trait_id, subst.fold_with(folder)?)
+            }
+            &TypeKind::Projection(assoc_type_id, ref subst) => {
+                TypeKind::Projection(assoc_type_id, subst.fold_with(folder)?)
+            }
+        };
+        Ok(Type::new(interner, ty_kind))
+    }
+}
+
+impl<I: Interner> TypeFoldable<I> for Type<I> {
+    fn fold_with<F: TypeFolder<I> + ?Sized>(&self, folder: &mut F) -> Result<Self, F::Error> {
+        folder.fold_type(self)
+    }
+}
+
+impl<I: Interner> TypeFoldable<I> for GenericArg<I> {
+    fn fold_with<F: TypeFolder<I> + ?Sized>(&self, folder: &mut F) -> Result<Self, F::Error> {
+        let interner = folder.interner();
+        let data = match self.data(interner) {
+            GenericArgData::Type(ty) => GenericArgData::Type(ty.fold_with(folder)?),
+            &GenericArgData::Const(value) => GenericArgData::Const(folder.fold_const(value)?),
+        };
+        Ok(GenericArg::new(interner, data))
+    }
+}
+
+impl<I: Interner> TypeFoldable<I> for Substitution<I> {
+    fn fold_with<F: TypeFolder<I> + ?Sized>(&self, folder: &mut F) -> Result<Self, F::Error> {
+        let interner = folder.interner();
+        let args = self
+            .data(interner)
+            .iter()
+            .map(|arg| arg.fold_with(folder))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Substitution::new(interner, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        add_items,
+        interner::AdtId,
+        test_interner::{TestInterner, type_of_every_kind},
+    };
+
+    type Interner = &'static TestInterner;
+
+    /// Replaces every occurrence of an ADT with a type.
+    struct ReplaceAdt {
+        interner: Interner,
+        adt_id: AdtId<Interner>,
+        replacement: Type<Interner>,
+    }
+
+    impl TypeFolder<Interner> for ReplaceAdt {
+        type Error = ();
+
+        fn interner(&self) -> Interner {
+            self.interner
+        }
+
+        fn fold_type(&mut self, ty: &Type<Interner>) -> Result<Type<Interner>, ()> {
+            match ty.kind(self.interner) {
+                TypeKind::Adt(adt_id, _) if *adt_id == self.adt_id => Ok(self.replacement.clone()),
+                _ => ty.super_fold_with(self),
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn folders_rebuild_every_kind_of_type() {
+        let interner = TestInterner::new();
+        let (A, B, C, PartialEq, (_Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct C;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        let every_kind_of = |adt_id| {
+            let ty = Type::adt(interner, adt_id, []);
+            type_of_every_kind(interner, ty, B, PartialEq, Item)
+        };
+        let mut folder = ReplaceAdt {
+            interner,
+            adt_id: A,
+            replacement: Type::adt(interner, C, []),
+        };
+        assert_eq!(
+            every_kind_of(A).fold_with(&mut folder),
+            Ok(every_kind_of(C))
+        );
+        // Types without the replaced ADT are rebuilt unchanged
+        assert_eq!(
+            every_kind_of(C).fold_with(&mut folder),
+            Ok(every_kind_of(C))
+        );
+    }
+
+    /// Adds one to every const.
+    struct IncrementConsts(Interner);
+
+    impl TypeFolder<Interner> for IncrementConsts {
+        type Error = ();
+
+        fn interner(&self) -> Interner {
+            self.0
+        }
+
+        fn fold_const(&mut self, value: ConstValue) -> Result<ConstValue, ()> {
+            Ok(ConstValue(value.0 + 1))
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn folders_rewrite_array_lengths_and_const_arguments() {
+        let interner = TestInterner::new();
+        let (A, Arr) = add_items!(*interner, {
+            struct A;
+            struct Arr<T, const N: usize>;
+        });
+        let a = Type::adt(interner, A, []);
+        let arr = |ty: &Type<_>, len| {
+            let args = [
+                GenericArg::ty(interner, ty.clone()),
+                GenericArg::constant(interner, ConstValue(len)),
+            ];
+            Type::adt(interner, Arr, args)
+        };
+        let ty = Type::array(interner, arr(&a, 2), ConstValue(3));
+        assert_eq!(
+            ty.fold_with(&mut IncrementConsts(interner)),
+            Ok(Type::array(interner, arr(&a, 3), ConstValue(4)))
+        );
+    }
+
+    /// Fails at the never type, counting types folded before.
+    struct FailAtNever {
+        interner: Interner,
+        folded: usize,
+    }
+
+    impl TypeFolder<Interner> for FailAtNever {
+        type Error = usize;
+
+        fn interner(&self) -> Interner {
+            self.interner
+        }
+
+        fn fold_type(&mut self, ty: &Type<Interner>) -> Result<Type<Interner>, usize> {
+            self.folded += 1;
+            match ty.kind(self.interner) {
+                TypeKind::Never => Err(self.folded),
+                _ => ty.super_fold_with(self),
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn folders_stop_at_the_first_error() {
+        let interner = TestInterner::new();
+        let (A, B, PartialEq, (_Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        let a = Type::adt(interner, A, []);
+        let ty = type_of_every_kind(interner, a, B, PartialEq, Item);
+        let mut folder = FailAtNever {
+            interner,
+            folded: 0,
+        };
+        // The tuple and its components up to `!` in prefix order, and nothing after `!`
+        assert_eq!(ty.fold_with(&mut folder), Err(17));
+        assert_eq!(folder.folded, 17);
+    }
+}
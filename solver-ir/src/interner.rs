@@ -1,10 +1,23 @@
 //! TODO: write docs
 
-use super::primitives::{AdtData, GenericArgData, TraitData, TypeData};
-use std::{fmt::Debug, marker::PhantomData, num::NonZero, ops::Deref};
+use super::{
+    patterns::PatternSeq,
+    primitives::{
+        AdtData, AssocTypeData, ConstValue, GenericArgData, ImplData, PtrQual, RefQual, Scalar,
+        TraitData, TypeData, TypeKind,
+    },
+};
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    num::NonZero,
+    ops::Deref,
+};
 
 /// TODO: write docs
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ItemId(pub NonZero<u32>);
 
 impl Deref for ItemId {
@@ -15,6 +28,38 @@ impl Deref for ItemId {
     }
 }
 
+/// Implements `PartialEq`, `Eq`, `Hash`, `PartialOrd` and `Ord` of an item id by comparing the
+/// `ItemId`, because derives would require the interner itself to implement them.
+macro_rules! impl_id_eq_ord {
+    ($name:ident) => {
+        impl<I: Interner> PartialEq for $name<I> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl<I: Interner> Eq for $name<I> {}
+
+        impl<I: Interner> Hash for $name<I> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state)
+            }
+        }
+
+        impl<I: Interner> PartialOrd for $name<I> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<I: Interner> Ord for $name<I> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+    };
+}
+
 /// TODO: write docs
 #[derive(Clone, Copy, Debug)]
 pub struct AdtId<I: Interner>(pub ItemId, PhantomData<I>);
@@ -33,11 +78,7 @@ impl<I: Interner> Deref for AdtId<I> {
     }
 }
 
-impl<I: Interner> PartialEq for AdtId<I> {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
-    }
-}
+impl_id_eq_ord!(AdtId);
 
 /// TODO: write docs
 #[derive(Clone, Copy, Debug)]
@@ -57,22 +98,61 @@ impl<I: Interner> Deref for TraitId<I> {
     }
 }
 
-impl<I: Interner> PartialEq for TraitId<I> {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
+impl_id_eq_ord!(TraitId);
+
+/// Identity of an associated type declared by a trait.
+#[derive(Clone, Copy, Debug)]
+pub struct AssocTypeId<I: Interner>(pub ItemId, PhantomData<I>);
+
+impl<I: Interner> AssocTypeId<I> {
+    pub fn new(id: ItemId) -> Self {
+        Self(id, PhantomData)
     }
 }
 
+impl<I: Interner> Deref for AssocTypeId<I> {
+    type Target = ItemId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl_id_eq_ord!(AssocTypeId);
+
+/// TODO: write docs
+#[derive(Clone, Copy, Debug)]
+pub struct ImplId<I: Interner>(pub ItemId, PhantomData<I>);
+
+impl<I: Interner> ImplId<I> {
+    pub fn new(id: ItemId) -> Self {
+        Self(id, PhantomData)
+    }
+}
+
+impl<I: Interner> Deref for ImplId<I> {
+    type Target = ItemId;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl_id_eq_ord!(ImplId);
+
 /// TODO: write docs
 pub trait Interner: Debug + Copy {
-    /// TODO: write docs
-    type InternedType: Debug;
+    /// Handle of an interned `TypeData`.
+    ///
+    /// Handles are compared and hashed in place of the data they refer to, so interners are
+    /// expected to deduplicate structurally equal data.
+    type InternedType: Debug + Clone + Eq + Hash;
 
-    /// TODO: write docs
-    type InternedGenericArg: Debug;
+    /// Handle of an interned `GenericArgData`, see `InternedType`.
+    type InternedGenericArg: Debug + Clone + Eq + Hash;
 
-    /// TODO: write docs
-    type InternedSubstitution: Debug;
+    /// Handle of an interned slice of `GenericArg`s, see `InternedType`.
+    type InternedSubstitution: Debug + Clone + Eq + Hash;
 
     /// TODO: write docs
     type InternedAdtData: Debug;
@@ -80,6 +160,21 @@ pub trait Interner: Debug + Copy {
     /// TODO: write docs
     type InternedTraitData: Debug;
 
+    /// TODO: write docs
+    type InternedImplData: Debug;
+
+    /// TODO: write docs
+    type InternedAssocTypeData: Debug;
+
+    /// Returns handle of `data`, which is equal to handles of structurally equal types.
+    fn intern_type(self, data: TypeData<Self>) -> Self::InternedType;
+
+    /// Returns handle of `data`, which is equal to handles of structurally equal generic args.
+    fn intern_generic_arg(self, data: GenericArgData<Self>) -> Self::InternedGenericArg;
+
+    /// Returns handle of `args`, which is equal to handles of structurally equal substitutions.
+    fn intern_substitution(self, args: Box<[GenericArg<Self>]>) -> Self::InternedSubstitution;
+
     /// TODO: write docs
     fn type_data(self, ty: &Self::InternedType) -> &TypeData<Self>;
 
@@ -93,41 +188,321 @@ pub trait Interner: Debug + Copy {
     fn adt_data(self, adt: &Self::InternedAdtData) -> &AdtData;
 
     /// TODO: write docs
-    fn trait_data(self, r#trait: &Self::InternedTraitData) -> &TraitData;
+    fn trait_data(self, r#trait: &Self::InternedTraitData) -> &TraitData<Self>;
+
+    /// TODO: write docs
+    fn impl_data(self, r#impl: &Self::InternedImplData) -> &ImplData<Self>;
+
+    /// TODO: write docs
+    fn assoc_type_data(self, assoc_type: &Self::InternedAssocTypeData) -> &AssocTypeData<Self>;
 
     /// TODO: write docs
     fn get_adt_by_id(self, id: AdtId<Self>) -> Self::InternedAdtData;
 
     /// TODO: write docs
     fn get_trait_by_id(self, id: TraitId<Self>) -> Self::InternedTraitData;
+
+    /// TODO: write docs
+    fn get_impl_by_id(self, id: ImplId<Self>) -> Self::InternedImplData;
+
+    /// TODO: write docs
+    fn get_assoc_type_by_id(self, id: AssocTypeId<Self>) -> Self::InternedAssocTypeData;
+
+    /// Returns ids of all ADTs in the order they were added.
+    fn adts(self) -> Vec<AdtId<Self>>;
+
+    /// Returns ids of all traits in the order they were added.
+    fn traits(self) -> Vec<TraitId<Self>>;
+
+    /// Returns ids of associated types declared by provided trait in the order they were added.
+    fn trait_assoc_types(self, trait_id: TraitId<Self>) -> Vec<AssocTypeId<Self>>;
+
+    /// Returns ids of all impls of provided trait in the order they were added.
+    fn trait_impls(self, trait_id: TraitId<Self>) -> Vec<ImplId<Self>>;
+
+    /// Returns ids of impls of provided trait whose headers may unify with `pattern`, in the
+    /// order they were added.
+    ///
+    /// `pattern` has the layout of a trait impl pattern. Returned impls still have to be unified
+    /// with `pattern`, which allows interners to look them up in an index such as
+    /// `DiscriminationTree`. By default all impls of the trait are returned.
+    fn candidate_impls(
+        self,
+        trait_id: TraitId<Self>,
+        pattern: &PatternSeq<Self>,
+    ) -> Vec<ImplId<Self>> {
+        let _ = pattern;
+        self.trait_impls(trait_id)
+    }
+
+    /// Returns ids of all inherent impls in the order they were added.
+    fn inherent_impls(self) -> Vec<ImplId<Self>>;
 }
 
-/// TODO: write docs
+/// Implements `PartialEq`, `Eq` and `Hash` of a handle wrapper by comparing interned handles,
+/// because derives would require the interner itself to implement them.
+macro_rules! impl_handle_eq_hash {
+    ($name:ident) => {
+        impl<I: Interner> PartialEq for $name<I> {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl<I: Interner> Eq for $name<I> {}
+
+        impl<I: Interner> Hash for $name<I> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.0.hash(state)
+            }
+        }
+    };
+}
+
+/// Type interned in an `Interner`.
+///
+/// Equality and hashing are the ones of interned handles, so they are cheap for interners that
+/// deduplicate types.
 #[derive(Clone, Debug)]
 pub struct Type<I: Interner>(I::InternedType);
 
+impl_handle_eq_hash!(Type);
+
 impl<I: Interner> Type<I> {
+    /// Interns type of provided kind.
+    pub fn new(interner: I, ty_kind: TypeKind<I>) -> Self {
+        Self(interner.intern_type(TypeData { ty_kind }))
+    }
+
     pub fn data(&self, interner: I) -> &TypeData<I> {
         interner.type_data(&self.0)
     }
+
+    pub fn kind(&self, interner: I) -> &TypeKind<I> {
+        &self.data(interner).ty_kind
+    }
+
+    // Builders below don't check arguments against generic parameters of the referenced items.
+
+    /// Creates ADT type `Name<Args>`.
+    pub fn adt(
+        interner: I,
+        adt_id: AdtId<I>,
+        args: impl IntoIterator<Item = GenericArg<I>>,
+    ) -> Self {
+        Self::new(
+            interner,
+            TypeKind::Adt(adt_id, Substitution::new(interner, args)),
+        )
+    }
+
+    pub fn scalar(interner: I, scalar: Scalar) -> Self {
+        Self::new(interner, TypeKind::Scalar(scalar))
+    }
+
+    /// Creates `NonZero<T>` type.
+    pub fn non_zero(interner: I, ty: Self) -> Self {
+        Self::new(interner, TypeKind::NonZero(ty))
+    }
+
+    /// Creates slice type `[T]`.
+    pub fn slice(interner: I, ty: Self) -> Self {
+        Self::new(interner, TypeKind::Slice(ty))
+    }
+
+    /// Creates array type `[T; N]`.
+    pub fn array(interner: I, ty: Self, len: ConstValue) -> Self {
+        Self::new(interner, TypeKind::Array(ty, len))
+    }
+
+    /// Creates tuple type `(A, B, ..)`.
+    pub fn tuple(interner: I, tys: impl IntoIterator<Item = Self>) -> Self {
+        Self::new(
+            interner,
+            TypeKind::Tuple(Substitution::from_types(interner, tys)),
+        )
+    }
+
+    /// Creates unit type `()`.
+    pub fn unit(interner: I) -> Self {
+        Self::tuple(interner, [])
+    }
+
+    /// Creates reference type `&T`, `&mut T` or `&drop T`.
+    pub fn reference(interner: I, qual: Option<RefQual>, ty: Self) -> Self {
+        Self::new(interner, TypeKind::Ref(qual, ty))
+    }
+
+    /// Creates pointer type `*T` or `*mut T`.
+    pub fn ptr(interner: I, qual: Option<PtrQual>, ty: Self) -> Self {
+        Self::new(interner, TypeKind::Ptr(qual, ty))
+    }
+
+    /// Creates never type `!`.
+    pub fn never(interner: I) -> Self {
+        Self::new(interner, TypeKind::Never)
+    }
+
+    /// Creates function pointer type `fn(A, B, ..) -> R`.
+    pub fn fn_ptr(interner: I, params: impl IntoIterator<Item = Self>, ret: Self) -> Self {
+        let tys = params.into_iter().chain(std::iter::once(ret));
+        Self::new(
+            interner,
+            TypeKind::FnPtr(Substitution::from_types(interner, tys)),
+        )
+    }
+
+    /// Creates trait object type `dyn Trait<Args>`.
+    pub fn dyn_trait(
+        interner: I,
+        trait_id: TraitId<I>,
+        args: impl IntoIterator<Item = GenericArg<I>>,
+    ) -> Self {
+        Self::new(
+            interner,
+            TypeKind::Dyn(trait_id, Substitution::new(interner, args)),
+        )
+    }
+
+    /// Creates projection type `<Self as Trait<Args>>::Name`.
+    pub fn projection(
+        interner: I,
+        assoc_type_id: AssocTypeId<I>,
+        self_ty: Self,
+        trait_args: impl IntoIterator<Item = GenericArg<I>>,
+    ) -> Self {
+        let args = std::iter::once(GenericArg::ty(interner, self_ty)).chain(trait_args);
+        Self::new(
+            interner,
+            TypeKind::Projection(assoc_type_id, Substitution::new(interner, args)),
+        )
+    }
 }
 
-/// TODO: write docs
+/// Generic arguments interned in an `Interner`, see `Type` for equality.
 #[derive(Clone, Debug)]
 pub struct Substitution<I: Interner>(I::InternedSubstitution);
 
+impl_handle_eq_hash!(Substitution);
+
 impl<I: Interner> Substitution<I> {
+    /// Interns substitution of provided generic arguments.
+    pub fn new(interner: I, args: impl IntoIterator<Item = GenericArg<I>>) -> Self {
+        Self(interner.intern_substitution(args.into_iter().collect()))
+    }
+
+    /// Interns substitution of provided types.
+    pub fn from_types(interner: I, tys: impl IntoIterator<Item = Type<I>>) -> Self {
+        Self::new(
+            interner,
+            tys.into_iter().map(|ty| GenericArg::ty(interner, ty)),
+        )
+    }
+
+    pub fn empty(interner: I) -> Self {
+        Self::new(interner, [])
+    }
+
     pub fn data(&self, interner: I) -> &[GenericArg<I>] {
         interner.substitution_data(&self.0)
     }
 }
 
-/// TODO: write docs
+/// Generic argument interned in an `Interner`, see `Type` for equality.
 #[derive(Clone, Debug)]
 pub struct GenericArg<I: Interner>(I::InternedGenericArg);
 
+impl_handle_eq_hash!(GenericArg);
+
 impl<I: Interner> GenericArg<I> {
+    /// Interns provided generic argument.
+    pub fn new(interner: I, data: GenericArgData<I>) -> Self {
+        Self(interner.intern_generic_arg(data))
+    }
+
+    /// Interns type argument.
+    pub fn ty(interner: I, ty: Type<I>) -> Self {
+        Self::new(interner, GenericArgData::Type(ty))
+    }
+
+    /// Interns const argument.
+    pub fn constant(interner: I, value: ConstValue) -> Self {
+        Self::new(interner, GenericArgData::Const(value))
+    }
+
     pub fn data(&self, interner: I) -> &GenericArgData<I> {
         interner.generic_arg_data(&self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, primitives::UIntType, test_interner::TestInterner};
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn builders_create_types_of_their_kind() {
+        let interner = TestInterner::new();
+        let (A, B, (Add, Output)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait Add<Rhs> {
+                type Output;
+            }
+        });
+        let a = Type::adt(interner, A, []);
+        let u8 = Type::scalar(interner, Scalar::UInt(UIntType::u8));
+        let types =
+            |tys: &[&Type<_>]| Substitution::from_types(interner, tys.iter().copied().cloned());
+        assert_eq!(
+            *Type::adt(interner, B, [GenericArg::ty(interner, a.clone())]).kind(interner),
+            TypeKind::Adt(B, types(&[&a]))
+        );
+        assert_eq!(
+            *Type::tuple(interner, [a.clone(), u8.clone()]).kind(interner),
+            TypeKind::Tuple(types(&[&a, &u8]))
+        );
+        assert_eq!(
+            *Type::unit(interner).kind(interner),
+            TypeKind::Tuple(Substitution::empty(interner))
+        );
+        // The return type follows the parameter types
+        let never = Type::never(interner);
+        assert_eq!(
+            *Type::fn_ptr(interner, [a.clone(), u8.clone()], never.clone()).kind(interner),
+            TypeKind::FnPtr(types(&[&a, &u8, &never]))
+        );
+        assert_eq!(
+            *Type::fn_ptr(interner, [], a.clone()).kind(interner),
+            TypeKind::FnPtr(types(&[&a]))
+        );
+        assert_eq!(
+            *Type::dyn_trait(interner, Add, [GenericArg::ty(interner, u8.clone())]).kind(interner),
+            TypeKind::Dyn(Add, types(&[&u8]))
+        );
+        // The self type precedes generic arguments of the trait
+        assert_eq!(
+            *Type::projection(
+                interner,
+                Output,
+                a.clone(),
+                [GenericArg::ty(interner, u8.clone())]
+            )
+            .kind(interner),
+            TypeKind::Projection(Output, types(&[&a, &u8]))
+        );
+        assert_eq!(
+            *Type::array(interner, u8.clone(), ConstValue(4)).kind(interner),
+            TypeKind::Array(u8.clone(), ConstValue(4))
+        );
+        assert_eq!(
+            *Type::reference(interner, Some(RefQual::Mut), a.clone()).kind(interner),
+            TypeKind::Ref(Some(RefQual::Mut), a.clone())
+        );
+        assert_eq!(
+            *Type::ptr(interner, None, a.clone()).kind(interner),
+            TypeKind::Ptr(None, a)
+        );
+    }
+}
@@ -1,37 +1,114 @@
+pub mod coherence;
+pub mod discrimination_tree;
+pub mod elaborate;
+pub mod fold;
 pub mod interner;
+pub mod lowering;
+pub mod param_env;
+pub mod parse;
 pub mod patterns;
 pub mod primitives;
+pub mod solver;
+#[cfg(test)]
+mod test_interner;
+pub mod unify;
+pub mod visit;
 
 #[macro_export]
 macro_rules! param_to_kind {
     ($ident:ident) => {
         $crate::primitives::GenericArgType::Type
     };
+    // Type of const parameters isn't tracked, see `ConstValue`
+    (const $ident:ident : $ty:ty) => {
+        $crate::primitives::GenericArgType::Const
+    };
 }
 
+// Generic parameters are matched as `$params $($const_params)? $(: $param_tys)?`, because `const`
+// of `const N: usize` is matched by `ident` fragments as well
 #[macro_export]
 macro_rules! add_item {
-    ($interner:expr, struct $name:ident< $($params:ident),* >) => {
+    (
+        $interner:expr,
+        struct $name:ident< $( $params:ident $($const_params:ident)? $(: $param_tys:ty)? ),* >
+    ) => {
         $interner.new_adt(
             stringify!($name).into(),
-            [$($crate::param_to_kind!($params)),*].into()
+            [$($crate::param_to_kind!($params $($const_params)? $(: $param_tys)?)),*].into()
         )
     };
-    ($interner:expr, trait $name:ident< $($params:ident),* >) => {
+    (
+        $interner:expr,
+        trait $name:ident< $( $params:ident $($const_params:ident)? $(: $param_tys:ty)? ),* >
+        $(
+            : $supertrait:ident $(< $($supertrait_args:ty),* >)?
+            $(+ $supertraits:ident $(< $($supertraits_args:ty),* >)?)*
+        )?
+    ) => {{
+        // Supertraits are parsed as impl headers declaring `Self` and parameters of the trait
+        #[allow(unused_variables)]
+        let generics = concat!("impl<Self", $(", ", stringify!($params $($const_params)?),)* ">");
         $interner.new_trait(
             stringify!($name).into(),
-            [$($crate::param_to_kind!($params)),*].into()
+            [$($crate::param_to_kind!($params $($const_params)? $(: $param_tys)?)),*].into(),
+            [$(
+                $crate::primitives::WhereClause::parse(
+                    &$interner,
+                    &format!(
+                        "{} Self as {}",
+                        generics,
+                        stringify!($supertrait $(< $($supertrait_args),* >)?)
+                    ),
+                )
+                .expect("supertrait should be a bound of the trait"),
+                $(
+                    $crate::primitives::WhereClause::parse(
+                        &$interner,
+                        &format!(
+                            "{} Self as {}",
+                            generics,
+                            stringify!($supertraits $(< $($supertraits_args),* >)?)
+                        ),
+                    )
+                    .expect("supertrait should be a bound of the trait"),
+                )*
+            )?].into()
         )
-    };
+        .expect("supertraits should refer only to generic parameters of the trait")
+    }};
 }
 
+// Items are added one by one, so that traits can refer to previously added traits as supertraits
 #[macro_export]
 macro_rules! add_items {
-    ($interner:expr, { $( $kinds:ident $items:ident $(< $($params:ident),* >)?; )* }) => {
-        (
+    (
+        $interner:expr,
+        {
             $(
-                $crate::add_item!($interner, $kinds $items <$($( $params ),*)?> )
-            ),*
-        )
-    };
+                $kinds:ident $items:ident
+                $(< $( $params:ident $($const_params:ident)? $(: $param_tys:ty)? ),* >)?
+                $(
+                    : $supertrait:ident $(< $($supertrait_args:ty),* >)?
+                    $(+ $supertraits:ident $(< $($supertraits_args:ty),* >)?)*
+                )?
+                $(; )? $({ $( type $assoc_types:ident; )* })?
+            )*
+        }
+    ) => {{
+        $(
+            let $items = $crate::add_item!(
+                $interner,
+                $kinds $items <$($( $params $($const_params)? $(: $param_tys)? ),*)?>
+                $(
+                    : $supertrait $(< $($supertrait_args),* >)?
+                    $(+ $supertraits $(< $($supertraits_args),* >)?)*
+                )?
+            );
+            $($(
+                let $assoc_types = $interner.new_assoc_type($items, stringify!($assoc_types).into());
+            )*)?
+        )*
+        ( $( ( $items $(, $($assoc_types),*)? ) ),* )
+    }};
 }
@@ -0,0 +1,268 @@
+//! Conversions between interned types and patterns.
+//!
+//! Types built by a frontend are trees of `TypeKind`s, while impls are matched against flat
+//! patterns. Lowering encodes a type as an `ExactPatternSeq` of a single pattern, so it can be used
+//! as a goal. Lifting goes the other way and succeeds only for patterns without placeholders,
+//! because types have no way to express them.
+
+use crate::{
+    interner::{GenericArg, Interner, Substitution, Type},
+    patterns::{ExactPattern, ExactPatternSeq, PatternElement, PatternError, PatternSeq},
+    primitives::{GenericArgData, TypeId, TypeKind},
+};
+
+impl<I: Interner> Type<I> {
+    /// Encodes `self` as a pattern.
+    ///
+    /// Fails if `self` doesn't fit generic parameters of the items it refers to, e.g. an ADT with
+    /// a wrong number of arguments.
+    pub fn lower(&self, interner: I) -> Result<Box<ExactPatternSeq<I>>, PatternError> {
+        let mut elems = Vec::new();
+        lower_type_into(interner, self, &mut elems);
+        exact_pattern_seq(interner, &elems)
+    }
+
+    /// Encodes goal `self: Trait<trait_args>` as a pattern with the layout of a trait impl.
+    ///
+    /// Fails in the same cases as `lower`.
+    pub fn lower_with_trait_args(
+        &self,
+        interner: I,
+        trait_args: &[GenericArg<I>],
+    ) -> Result<Box<ExactPatternSeq<I>>, PatternError> {
+        let mut elems = Vec::new();
+        lower_type_into(interner, self, &mut elems);
+        for arg in trait_args {
+            lower_generic_arg_into(interner, arg, &mut elems);
+        }
+        exact_pattern_seq(interner, &elems)
+    }
+}
+
+impl<I: Interner> GenericArg<I> {
+    /// Encodes `self` as a pattern, see `Type::lower`.
+    pub fn lower(&self, interner: I) -> Result<Box<ExactPatternSeq<I>>, PatternError> {
+        let mut elems = Vec::new();
+        lower_generic_arg_into(interner, self, &mut elems);
+        exact_pattern_seq(interner, &elems)
+    }
+}
+
+impl<I: Interner> ExactPattern<I> {
+    /// Decodes type pattern `self` into a type.
+    ///
+    /// Returns `None` if `self` is a const pattern or contains placeholders.
+    pub fn lift_type(&self, interner: I) -> Option<Type<I>> {
+        let &PatternElement::TypeConstructor { type_id, .. } = self.first() else {
+            return None;
+        };
+        let mut args = Vec::new();
+        if let Some(pattern_args) = self.args() {
+            for arg in pattern_args {
+                args.push(arg.lift_generic_arg(interner)?);
+            }
+        }
+        let ty_kind = match type_id {
+            TypeId::Adt(adt_id) => TypeKind::Adt(adt_id, Substitution::new(interner, args)),
+            TypeId::Scalar(scalar) => TypeKind::Scalar(scalar),
+            TypeId::NonZero => TypeKind::NonZero(single_type(interner, &args)?),
+            TypeId::Slice => TypeKind::Slice(single_type(interner, &args)?),
+            TypeId::Array => {
+                let [ty, len] = &args[..] else {
+                    return None;
+                };
+                let (GenericArgData::Type(ty), &GenericArgData::Const(len)) =
+                    (ty.data(interner), len.data(interner))
+                else {
+                    return None;
+                };
+                TypeKind::Array(ty.clone(), len)
+            }
+            TypeId::Tuple(_) => TypeKind::Tuple(Substitution::new(interner, args)),
+            TypeId::Ref(qual) => TypeKind::Ref(qual, single_type(interner, &args)?),
+            TypeId::Ptr(qual) => TypeKind::Ptr(qual, single_type(interner, &args)?),
+            TypeId::Never => TypeKind::Never,
+            TypeId::FnPtr(_) => TypeKind::FnPtr(Substitution::new(interner, args)),
+            TypeId::Dyn(trait_id) => TypeKind::Dyn(trait_id, Substitution::new(interner, args)),
+            TypeId::Projection(assoc_type_id) => {
+                TypeKind::Projection(assoc_type_id, Substitution::new(interner, args))
+            }
+        };
+        Some(Type::new(interner, ty_kind))
+    }
+
+    /// Decodes `self` into a generic argument.
+    ///
+    /// Returns `None` if `self` contains placeholders.
+    pub fn lift_generic_arg(&self, interner: I) -> Option<GenericArg<I>> {
+        match *self.first() {
+            PatternElement::ConstValue(value) => Some(GenericArg::constant(interner, value)),
+            PatternElement::TypeConstructor { .. } => {
+                Some(GenericArg::ty(interner, self.lift_type(interner)?))
+            }
+            PatternElement::TypePlaceholder(_)
+            | PatternElement::InferredType(_)
+            | PatternElement::ConstPlaceholder(_)
+            | PatternElement::InferredConst(_) => None,
+        }
+    }
+}
+
+fn single_type<I: Interner>(interner: I, args: &[GenericArg<I>]) -> Option<Type<I>> {
+    match args {
+        [arg] => match arg.data(interner) {
+            GenericArgData::Type(ty) => Some(ty.clone()),
+            GenericArgData::Const(_) => None,
+        },
+        _ => None,
+    }
+}
+
+fn exact_pattern_seq<I: Interner>(
+    interner: I,
+    elems: &[PatternElement<I>],
+) -> Result<Box<ExactPatternSeq<I>>, PatternError> {
+    let seq = PatternSeq::new(interner, elems)?;
+    // Safe because types have no way to express inference variables
+    Ok(unsafe { ExactPatternSeq::new_unchecked(seq) }.boxed())
+}
+
+fn lower_generic_arg_into<I: Interner>(
+    interner: I,
+    arg: &GenericArg<I>,
+    out: &mut Vec<PatternElement<I>>,
+) {
+    match arg.data(interner) {
+        GenericArgData::Type(ty) => lower_type_into(interner, ty, out),
+        &GenericArgData::Const(value) => out.push(PatternElement::ConstValue(value)),
+    }
+}
+
+fn lower_type_into<I: Interner>(interner: I, ty: &Type<I>, out: &mut Vec<PatternElement<I>>) {
+    let start = out.len();
+    // Placeholder for the type constructor, which is written once the length of args is known
+    out.push(PatternElement::TypeConstructor {
+        args_length: 0,
+        type_id: TypeId::Never,
+    });
+    let type_id = match ty.kind(interner) {
+        TypeKind::Adt(adt_id, subst) => {
+            for arg in subst.data(interner) {
+                lower_generic_arg_into(interner, arg, out);
+            }
+            TypeId::Adt(*adt_id)
+        }
+        &TypeKind::Scalar(scalar) => TypeId::Scalar(scalar),
+        TypeKind::NonZero(ty) => {
+            lower_type_into(interner, ty, out);
+            TypeId::NonZero
+        }
+        TypeKind::Slice(ty) => {
+            lower_type_into(interner, ty, out);
+            TypeId::Slice
+        }
+        &TypeKind::Array(ref ty, len) => {
+            lower_type_into(interner, ty, out);
+            out.push(PatternElement::ConstValue(len));
+            TypeId::Array
+        }
+        TypeKind::Tuple(subst) => {
+            let tys = subst.data(interner);
+            for arg in tys {
+                lower_generic_arg_into(interner, arg, out);
+            }
+            TypeId::Tuple(tys.len())
+        }
+        &TypeKind::Ref(qual, ref ty) => {
+            lower_type_into(interner, ty, out);
+            TypeId::Ref(qual)
+        }
+        &TypeKind::Ptr(qual, ref ty) => {
+            lower_type_into(interner, ty, out);
+            TypeId::Ptr(qual)
+        }
+        TypeKind::Never => TypeId::Never,
+        TypeKind::FnPtr(subst) => {
+            let tys = subst.data(interner);
+            for arg in tys {
+                lower_generic_arg_into(interner, arg, out);
+            }
+            // The last type is the return type. Invalid empty substitution is rejected when the
+            // pattern is checked, because it yields an arity that doesn't match its arguments.
+            TypeId::FnPtr(tys.len().saturating_sub(1))
+        }
+        TypeKind::Dyn(trait_id, subst) => {
+            for arg in subst.data(interner) {
+                lower_generic_arg_into(interner, arg, out);
+            }
+            TypeId::Dyn(*trait_id)
+        }
+        TypeKind::Projection(assoc_type_id, subst) => {
+            for arg in subst.data(interner) {
+                lower_generic_arg_into(interner, arg, out);
+            }
+            TypeId::Projection(*assoc_type_id)
+        }
+    };
+    out[start] = PatternElement::TypeConstructor {
+        args_length: out.len() - start - 1,
+        type_id,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, test_interner::TestInterner};
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn types_round_trip_through_patterns() {
+        let interner = TestInterner::new();
+        let (A, B, _PartialEq, (_Iterator, _Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        for text in [
+            "A",
+            "B<[u8; 3]>",
+            "fn(A, &drop [i32]) -> (A, !)",
+            "dyn PartialEq<A>",
+            "&mut *B<NonZero<u64>>",
+            "<A as Iterator>::Item",
+        ] {
+            let pattern = PatternSeq::parse(interner, text).unwrap();
+            let pattern = ExactPatternSeq::new(&pattern).unwrap();
+            let ty = pattern.split_first().0.lift_type(interner).unwrap();
+            assert_eq!(
+                ty.lower(interner).unwrap()[..],
+                pattern[..],
+                "type `{}`",
+                text
+            );
+        }
+        for text in ["B<T0>", "[A; N0]", "3"] {
+            let pattern = PatternSeq::parse(interner, text).unwrap();
+            let pattern = ExactPatternSeq::new(&pattern).unwrap();
+            assert!(pattern.split_first().0.lift_type(interner).is_none());
+        }
+        let a = Type::adt(interner, A, []);
+        assert_eq!(
+            Type::adt(
+                interner,
+                B,
+                [
+                    GenericArg::ty(interner, a.clone()),
+                    GenericArg::ty(interner, a)
+                ]
+            )
+            .lower(interner)
+            .unwrap_err(),
+            PatternError::ArityMismatch { index: 0, arity: 1 }
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Parameter environments.
+//!
+//! Generic code is checked with its generic parameters replaced by placeholders. Nothing is known
+//! about a placeholder except the where-clauses that are in scope, e.g. `T0: Clone` inside of
+//! `fn f<T0: Clone>()` or inside of `impl<T0> B<T0> as Clone where T0: Clone`. `ParamEnv` holds
+//! these where-clauses, so that the solver can use them as assumptions.
+
+use crate::{
+    elaborate::elaborate,
+    interner::{ImplId, Interner},
+    primitives::WhereClause,
+    solver::TraitGoal,
+    unify::InferenceTable,
+};
+
+/// Where-clauses that are assumed to hold, together with every bound implied by them.
+#[derive(Debug)]
+pub struct ParamEnv<I: Interner> {
+    clauses: Box<[WhereClause<I>]>,
+}
+
+impl<I: Interner> Default for ParamEnv<I> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<I: Interner> ParamEnv<I> {
+    /// Creates environment without any assumptions, e.g. for checking non-generic code.
+    pub fn empty() -> Self {
+        Self { clauses: [].into() }
+    }
+
+    /// Creates environment that assumes `where_clauses` and every bound implied by them through
+    /// supertraits.
+    ///
+    /// Placeholders of `where_clauses` are generic parameters of the item being checked.
+    pub fn new(interner: I, where_clauses: &[WhereClause<I>]) -> Self {
+        Self {
+            clauses: elaborate(interner, where_clauses).into(),
+        }
+    }
+
+    /// Creates environment of the body of `impl_id`, which assumes where-clauses of the impl.
+    pub fn for_impl(interner: I, impl_id: ImplId<I>) -> Self {
+        let impl_data = interner.get_impl_by_id(impl_id);
+        Self::new(interner, &interner.impl_data(&impl_data).where_clauses)
+    }
+
+    /// Returns elaborated where-clauses of the environment.
+    pub fn clauses(&self) -> &[WhereClause<I>] {
+        &self.clauses
+    }
+
+    /// Returns indices of clauses that may prove `goal`.
+    ///
+    /// Placeholders are rigid, so a clause applies only if it unifies with `goal` by binding
+    /// inference variables of `goal`.
+    pub fn matching_clauses(&self, goal: TraitGoal<'_, I>) -> Vec<usize> {
+        self.clauses
+            .iter()
+            .enumerate()
+            .filter(|(_, clause)| {
+                clause.trait_id == goal.trait_id
+                    && InferenceTable::new()
+                        .unify(goal.pattern, &clause.pattern)
+                        .is_ok()
+            })
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+}
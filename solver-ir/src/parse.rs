@@ -0,0 +1,833 @@
+//! Parser of patterns written in the syntax printed by `PatternSeq::format` and
+//! `PatternSeq::format_as_trait_impl`.
+//!
+//! Text is parsed into the same elements that the `impl_patterns!` macro produces, so impl headers
+//! can be loaded at runtime, e.g. from files. Names of ADTs, traits and associated types are
+//! resolved against an interner. Kinds of generic arguments are known from the items they are
+//! passed to, so `?` is an inferred const in const positions and an inferred type otherwise.
+//! Inference variables may be named, e.g. `?x`, to refer to the same variable more than once.
+//!
+//! Impl headers declare their placeholders as generic parameters, `impl<T, const N> ..`, which are
+//! numbered in order of declaration. Sequences without an impl header refer to placeholders as
+//! they are printed: `T{idx}` for types and `N{idx}` for consts.
+
+use crate::{
+    interner::{AdtId, AssocTypeId, Interner, TraitId},
+    patterns::{
+        ExactPatternSeq, InferenceVar, PatternElement, PatternKind, PatternSeq, PlaceholderIndex,
+    },
+    primitives::{
+        ConstValue, FloatType, GenericArgType, IntType, PtrQual, RefQual, Scalar, TypeId, UIntType,
+        WhereClause,
+    },
+};
+use std::{collections::HashMap, fmt::Display};
+
+/// Error of parsing a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the parsed text where the error was found.
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl<I: Interner> PatternSeq<I> {
+    /// Parses a sequence of patterns separated by commas, e.g. `B<T0>, 3, ?`.
+    ///
+    /// Kinds of patterns in the sequence aren't known, so `?` is parsed as an inferred type.
+    pub fn parse(interner: I, text: &str) -> Result<Box<Self>, ParseError> {
+        let mut parser = Parser::new(interner, text)?;
+        parser.parse_arg(None)?;
+        while parser.eat(",") {
+            parser.parse_arg(None)?;
+        }
+        parser.finish()
+    }
+
+    /// Parses trait impl header `impl<Params> Type as Trait<Args>`, and returns the trait
+    /// together with the pattern of the impl.
+    pub fn parse_trait_impl(
+        interner: I,
+        text: &str,
+    ) -> Result<(TraitId<I>, Box<Self>), ParseError> {
+        let mut parser = Parser::new(interner, text)?;
+        parser.parse_impl_header()?;
+        parser.expect_keyword("as")?;
+        let (position, name) = parser.expect_ident()?;
+        let (trait_id, generic_args) = parser.resolve_trait(position, name)?;
+        parser.parse_generic_args(name, &generic_args)?;
+        Ok((trait_id, parser.finish()?))
+    }
+
+    /// Parses inherent impl header `impl<Params> Type`.
+    pub fn parse_inherent_impl(interner: I, text: &str) -> Result<Box<Self>, ParseError> {
+        let mut parser = Parser::new(interner, text)?;
+        parser.parse_impl_header()?;
+        parser.finish()
+    }
+}
+
+impl<I: Interner> WhereClause<I> {
+    /// Parses bound written as a trait impl header, e.g. `impl<Self, T> Self as PartialEq<T>` for
+    /// `Self: PartialEq<T>` of `trait Eq<T>`, see `PatternSeq::parse_trait_impl`.
+    ///
+    /// Placeholders are numbered in order of declaration, so bounds of a trait declaration declare
+    /// `Self` followed by generic parameters of the trait, see `TraitData::supertraits`.
+    pub fn parse(interner: I, text: &str) -> Result<Self, ParseError> {
+        let (trait_id, pattern) = PatternSeq::parse_trait_impl(interner, text)?;
+        let pattern = ExactPatternSeq::new(&pattern).ok_or_else(|| ParseError {
+            position: text.find('?').unwrap_or(0),
+            message: "where-clauses can't contain inference variables".into(),
+        })?;
+        Ok(Self {
+            trait_id,
+            pattern: pattern.boxed(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Int(&'a str),
+    Punct(&'static str),
+    End,
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Ident(text) | Token::Int(text) => write!(f, "`{}`", text),
+            Token::Punct(punct) => write!(f, "`{}`", punct),
+            Token::End => write!(f, "end of input"),
+        }
+    }
+}
+
+// Longer punctuation goes first, so that e.g. `::` isn't split into two tokens
+const PUNCTS: [&str; 15] = [
+    "::", "->", "<", ">", ",", "(", ")", "[", "]", ";", "&", "*", "!", "?", ":",
+];
+
+/// Splits `text` into tokens together with their byte offsets. The last token is always
+/// `Token::End`.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        let position = text.len() - rest.len();
+        let word_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (token, len) = if word_len != 0 {
+            let word = &rest[..word_len];
+            if word.starts_with(|c: char| c.is_ascii_digit()) {
+                if !word.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(ParseError {
+                        position,
+                        message: format!("invalid const `{}`", word),
+                    });
+                }
+                (Token::Int(word), word_len)
+            } else {
+                (Token::Ident(word), word_len)
+            }
+        } else {
+            match PUNCTS.iter().find(|punct| rest.starts_with(**punct)) {
+                Some(&punct) => (Token::Punct(punct), punct.len()),
+                None => {
+                    let c = rest.chars().next().expect("`rest` shouldn't be empty");
+                    return Err(ParseError {
+                        position,
+                        message: format!("unexpected character `{}`", c),
+                    });
+                }
+            }
+        };
+        tokens.push((position, token));
+        rest = rest[len..].trim_start();
+    }
+    tokens.push((text.len(), Token::End));
+    Ok(tokens)
+}
+
+fn scalar_by_name(name: &str) -> Option<Scalar> {
+    Some(match name {
+        "bool" => Scalar::bool,
+        "char" => Scalar::char,
+        "uchar" => Scalar::uchar,
+        "i8" => Scalar::Int(IntType::i8),
+        "i16" => Scalar::Int(IntType::i16),
+        "i32" => Scalar::Int(IntType::i32),
+        "i64" => Scalar::Int(IntType::i64),
+        "isize" => Scalar::Int(IntType::isize),
+        "u8" => Scalar::UInt(UIntType::u8),
+        "u16" => Scalar::UInt(UIntType::u16),
+        "u32" => Scalar::UInt(UIntType::u32),
+        "u64" => Scalar::UInt(UIntType::u64),
+        "usize" => Scalar::UInt(UIntType::usize),
+        "f16" => Scalar::Float(FloatType::f16),
+        "f32" => Scalar::Float(FloatType::f32),
+        "f64" => Scalar::Float(FloatType::f64),
+        _ => return None,
+    })
+}
+
+struct Parser<'a, I: Interner> {
+    interner: I,
+    tokens: Vec<(usize, Token<'a>)>,
+    next: usize,
+    /// Placeholders declared by the impl header, or `None` if the text has no impl header.
+    generics: Option<HashMap<&'a str, PatternElement<I>>>,
+    inference_vars: u32,
+    named_inference_vars: HashMap<&'a str, InferenceVar>,
+    elems: Vec<PatternElement<I>>,
+}
+
+impl<'a, I: Interner> Parser<'a, I> {
+    fn new(interner: I, text: &'a str) -> Result<Self, ParseError> {
+        Ok(Self {
+            interner,
+            tokens: tokenize(text)?,
+            next: 0,
+            generics: None,
+            inference_vars: 0,
+            named_inference_vars: HashMap::new(),
+            elems: Vec::new(),
+        })
+    }
+
+    fn peek_nth(&self, n: usize) -> (usize, Token<'a>) {
+        let last = self.tokens.len() - 1;
+        self.tokens[(self.next + n).min(last)]
+    }
+
+    fn peek(&self) -> (usize, Token<'a>) {
+        self.peek_nth(0)
+    }
+
+    fn bump(&mut self) -> (usize, Token<'a>) {
+        let token = self.peek();
+        if token.1 != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, punct: &'static str) -> bool {
+        let found = self.peek().1 == Token::Punct(punct);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().1 == Token::Ident(keyword);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let (position, token) = self.peek();
+        ParseError {
+            position,
+            message: format!("expected {}, found {}", expected, token),
+        }
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), ParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", punct)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{}`", keyword)))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<(usize, &'a str), ParseError> {
+        match self.peek() {
+            (position, Token::Ident(name)) => {
+                self.bump();
+                Ok((position, name))
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    /// Checks that the whole text was parsed and returns the parsed sequence.
+    fn finish(self) -> Result<Box<PatternSeq<I>>, ParseError> {
+        if self.peek().1 != Token::End {
+            return Err(self.unexpected("end of input"));
+        }
+        let seq = PatternSeq::new(self.interner, &self.elems).map_err(|error| ParseError {
+            position: self.peek().0,
+            message: format!("invalid pattern: {}", error),
+        })?;
+        Ok(seq.boxed())
+    }
+
+    fn find_adt(&self, name: &str) -> Option<(AdtId<I>, Box<[GenericArgType]>)> {
+        self.interner.adts().into_iter().find_map(|adt_id| {
+            let adt_data = self.interner.get_adt_by_id(adt_id);
+            let adt_data = self.interner.adt_data(&adt_data);
+            (&*adt_data.name == name).then(|| (adt_id, adt_data.generic_args.clone()))
+        })
+    }
+
+    fn resolve_adt(
+        &self,
+        position: usize,
+        name: &str,
+    ) -> Result<(AdtId<I>, Box<[GenericArgType]>), ParseError> {
+        self.find_adt(name).ok_or_else(|| ParseError {
+            position,
+            message: format!("cannot find type `{}`", name),
+        })
+    }
+
+    fn resolve_trait(
+        &self,
+        position: usize,
+        name: &str,
+    ) -> Result<(TraitId<I>, Box<[GenericArgType]>), ParseError> {
+        self.interner
+            .traits()
+            .into_iter()
+            .find_map(|trait_id| {
+                let trait_data = self.interner.get_trait_by_id(trait_id);
+                let trait_data = self.interner.trait_data(&trait_data);
+                (&*trait_data.name == name).then(|| (trait_id, trait_data.generic_args.clone()))
+            })
+            .ok_or_else(|| ParseError {
+                position,
+                message: format!("cannot find trait `{}`", name),
+            })
+    }
+
+    fn resolve_assoc_type(
+        &self,
+        trait_id: TraitId<I>,
+        trait_name: &str,
+        position: usize,
+        name: &str,
+    ) -> Result<AssocTypeId<I>, ParseError> {
+        self.interner
+            .trait_assoc_types(trait_id)
+            .into_iter()
+            .find(|&assoc_type_id| {
+                let assoc_type_data = self.interner.get_assoc_type_by_id(assoc_type_id);
+                &*self.interner.assoc_type_data(&assoc_type_data).name == name
+            })
+            .ok_or_else(|| ParseError {
+                position,
+                message: format!("trait `{}` has no associated type `{}`", trait_name, name),
+            })
+    }
+
+    /// Returns placeholder named `name`, if there is one.
+    ///
+    /// Fails if there is no impl header and an ADT is named like a placeholder, e.g. `T0`, because
+    /// the name could refer to either of them.
+    fn placeholder(
+        &self,
+        position: usize,
+        name: &str,
+    ) -> Result<Option<PatternElement<I>>, ParseError> {
+        if let Some(generics) = &self.generics {
+            return Ok(generics.get(name).copied());
+        }
+        let (kind, idx) = match (name.strip_prefix('T'), name.strip_prefix('N')) {
+            (Some(idx), _) => (PatternKind::Type, idx),
+            (_, Some(idx)) => (PatternKind::Const, idx),
+            _ => return Ok(None),
+        };
+        let Ok(idx) = idx.parse() else {
+            return Ok(None);
+        };
+        if self.find_adt(name).is_some() {
+            return Err(ParseError {
+                position,
+                message: format!("`{}` may be either a placeholder or a type", name),
+            });
+        }
+        Ok(Some(PatternElement::new_placeholder(
+            kind,
+            PlaceholderIndex(idx),
+        )))
+    }
+
+    /// Parses `impl<Params> Type`, declaring placeholders of the impl.
+    fn parse_impl_header(&mut self) -> Result<(), ParseError> {
+        self.expect_keyword("impl")?;
+        let mut generics = HashMap::new();
+        // `impl <Type as Trait>::Name` is an impl for a projection rather than impl generics
+        let has_generics = self.peek().1 == Token::Punct("<")
+            && match self.peek_nth(1).1 {
+                Token::Punct(">") | Token::Ident("const") => true,
+                Token::Ident(_) => {
+                    matches!(self.peek_nth(2).1, Token::Punct(",") | Token::Punct(">"))
+                }
+                _ => false,
+            };
+        if has_generics {
+            self.bump();
+            while !self.eat(">") {
+                let kind = if self.eat_keyword("const") {
+                    PatternKind::Const
+                } else {
+                    PatternKind::Type
+                };
+                let (position, name) = self.expect_ident()?;
+                // Type of const parameters isn't tracked, see `ConstValue`
+                if kind == PatternKind::Const && self.eat(":") {
+                    self.expect_ident()?;
+                }
+                let idx = PlaceholderIndex(generics.len() as u32);
+                let placeholder = PatternElement::new_placeholder(kind, idx);
+                if generics.insert(name, placeholder).is_some() {
+                    return Err(ParseError {
+                        position,
+                        message: format!("generic parameter `{}` is declared twice", name),
+                    });
+                }
+                if self.peek().1 != Token::Punct(">") {
+                    self.expect(",")?;
+                }
+            }
+        }
+        self.generics = Some(generics);
+        self.parse_arg(Some(PatternKind::Type))
+    }
+
+    /// Parses a single pattern of provided kind, or of any kind if `kind` is `None`.
+    fn parse_arg(&mut self, kind: Option<PatternKind>) -> Result<(), ParseError> {
+        let (position, token) = self.peek();
+        let check_kind = |found: PatternKind| match kind {
+            Some(expected) if expected != found => Err(ParseError {
+                position,
+                message: format!("expected {}, found {}", expected, found),
+            }),
+            _ => Ok(()),
+        };
+        let placeholder = match token {
+            Token::Ident(name) => self.placeholder(position, name)?,
+            _ => None,
+        };
+        match token {
+            Token::Int(digits) => {
+                check_kind(PatternKind::Const)?;
+                self.bump();
+                let value = digits.parse().map_err(|_| ParseError {
+                    position,
+                    message: format!("const `{}` doesn't fit into 128 bits", digits),
+                })?;
+                self.elems
+                    .push(PatternElement::ConstValue(ConstValue(value)));
+            }
+            Token::Punct("?") => {
+                self.bump();
+                let var = match self.peek() {
+                    // Name must immediately follow `?`, e.g. `?x`
+                    (name_position, Token::Ident(name)) if name_position == position + 1 => {
+                        self.bump();
+                        *self.named_inference_vars.entry(name).or_insert_with(|| {
+                            self.inference_vars += 1;
+                            InferenceVar(self.inference_vars - 1)
+                        })
+                    }
+                    _ => {
+                        self.inference_vars += 1;
+                        InferenceVar(self.inference_vars - 1)
+                    }
+                };
+                let kind = kind.unwrap_or(PatternKind::Type);
+                self.elems
+                    .push(PatternElement::new_inference_var(kind, var));
+            }
+            _ => {
+                if let Some(placeholder) = placeholder {
+                    check_kind(placeholder.kind())?;
+                    self.bump();
+                    self.elems.push(placeholder);
+                } else {
+                    check_kind(PatternKind::Type)?;
+                    self.parse_type()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a type constructor applied to its arguments.
+    fn parse_type(&mut self) -> Result<(), ParseError> {
+        let start = self.elems.len();
+        // Placeholder for the type constructor, which is written once the length of args is known
+        self.elems.push(PatternElement::TypeConstructor {
+            args_length: 0,
+            type_id: TypeId::Never,
+        });
+        let (position, token) = self.peek();
+        let type_id = match token {
+            Token::Punct("!") => {
+                self.bump();
+                TypeId::Never
+            }
+            Token::Punct("(") => {
+                self.bump();
+                let mut arity = 0;
+                let mut trailing_comma = false;
+                while !self.eat(")") {
+                    if arity != 0 && !trailing_comma {
+                        return Err(self.unexpected("`,` or `)`"));
+                    }
+                    self.parse_arg(Some(PatternKind::Type))?;
+                    arity += 1;
+                    trailing_comma = self.eat(",");
+                }
+                if arity == 1 && !trailing_comma {
+                    // Parenthesized type rather than a single element tuple
+                    self.elems.remove(start);
+                    return Ok(());
+                }
+                TypeId::Tuple(arity)
+            }
+            Token::Punct("[") => {
+                self.bump();
+                self.parse_arg(Some(PatternKind::Type))?;
+                if self.eat(";") {
+                    self.parse_arg(Some(PatternKind::Const))?;
+                    self.expect("]")?;
+                    TypeId::Array
+                } else {
+                    self.expect("]")?;
+                    TypeId::Slice
+                }
+            }
+            Token::Punct("&") => {
+                self.bump();
+                let qual = if self.eat_keyword("mut") {
+                    Some(RefQual::Mut)
+                } else if self.eat_keyword("drop") {
+                    Some(RefQual::Drop)
+                } else {
+                    None
+                };
+                self.parse_arg(Some(PatternKind::Type))?;
+                TypeId::Ref(qual)
+            }
+            Token::Punct("*") => {
+                self.bump();
+                let qual = self.eat_keyword("mut").then_some(PtrQual::Mut);
+                self.parse_arg(Some(PatternKind::Type))?;
+                TypeId::Ptr(qual)
+            }
+            Token::Punct("<") => {
+                self.bump();
+                self.parse_arg(Some(PatternKind::Type))?;
+                self.expect_keyword("as")?;
+                let (trait_position, trait_name) = self.expect_ident()?;
+                let (trait_id, generic_args) = self.resolve_trait(trait_position, trait_name)?;
+                self.parse_generic_args(trait_name, &generic_args)?;
+                self.expect(">")?;
+                self.expect("::")?;
+                let (name_position, name) = self.expect_ident()?;
+                TypeId::Projection(self.resolve_assoc_type(
+                    trait_id,
+                    trait_name,
+                    name_position,
+                    name,
+                )?)
+            }
+            Token::Ident("fn") => {
+                self.bump();
+                self.expect("(")?;
+                let mut params = 0;
+                while !self.eat(")") {
+                    if params != 0 {
+                        self.expect(",")?;
+                        if self.eat(")") {
+                            break;
+                        }
+                    }
+                    self.parse_arg(Some(PatternKind::Type))?;
+                    params += 1;
+                }
+                if self.eat("->") {
+                    self.parse_arg(Some(PatternKind::Type))?;
+                } else {
+                    // Omitted return type is the unit type
+                    self.elems.push(PatternElement::TypeConstructor {
+                        args_length: 0,
+                        type_id: TypeId::Tuple(0),
+                    });
+                }
+                TypeId::FnPtr(params)
+            }
+            Token::Ident("dyn") => {
+                self.bump();
+                let (trait_position, trait_name) = self.expect_ident()?;
+                let (trait_id, generic_args) = self.resolve_trait(trait_position, trait_name)?;
+                self.parse_generic_args(trait_name, &generic_args)?;
+                TypeId::Dyn(trait_id)
+            }
+            Token::Ident("NonZero") => {
+                self.bump();
+                self.parse_generic_args("NonZero", &[GenericArgType::Type])?;
+                TypeId::NonZero
+            }
+            Token::Ident(name) => {
+                self.bump();
+                match scalar_by_name(name) {
+                    Some(scalar) => TypeId::Scalar(scalar),
+                    None => {
+                        let (adt_id, generic_args) = self.resolve_adt(position, name)?;
+                        self.parse_generic_args(name, &generic_args)?;
+                        TypeId::Adt(adt_id)
+                    }
+                }
+            }
+            _ => return Err(self.unexpected("type")),
+        };
+        self.elems[start] = PatternElement::TypeConstructor {
+            args_length: self.elems.len() - start - 1,
+            type_id,
+        };
+        Ok(())
+    }
+
+    /// Parses generic arguments of item named `name`, which are omitted if it has none.
+    fn parse_generic_args(
+        &mut self,
+        name: &str,
+        generic_args: &[GenericArgType],
+    ) -> Result<(), ParseError> {
+        let arg_count_error = |position| ParseError {
+            position,
+            message: format!(
+                "`{}` expects {} generic argument(s)",
+                name,
+                generic_args.len()
+            ),
+        };
+        if generic_args.is_empty() {
+            return match self.peek() {
+                (position, Token::Punct("<")) => Err(arg_count_error(position)),
+                _ => Ok(()),
+            };
+        }
+        if !self.eat("<") {
+            return Err(arg_count_error(self.peek().0));
+        }
+        for (i, &arg) in generic_args.iter().enumerate() {
+            if i != 0 && !self.eat(",") {
+                return match self.peek() {
+                    (position, Token::Punct(">")) => Err(arg_count_error(position)),
+                    _ => Err(self.unexpected("`,`")),
+                };
+            }
+            self.parse_arg(Some(arg.into()))?;
+        }
+        match self.peek() {
+            (_, Token::Punct(">")) => {
+                self.bump();
+                Ok(())
+            }
+            (position, Token::Punct(",")) => Err(arg_count_error(position)),
+            _ => Err(self.unexpected("`>`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, test_interner::TestInterner};
+
+    type Interner = &'static TestInterner;
+
+    /// Creates interner with ADTs `A`, `B<T>` and `Arr<T, const N: usize>`, and with traits
+    /// `Clone`, `PartialEq<Rhs>` and `Iterator { type Item; }`.
+    #[allow(non_snake_case)]
+    fn items() -> Interner {
+        let interner = TestInterner::new();
+        let (_A, _B, _Arr, _Clone, _PartialEq, (_Iterator, _Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct Arr<T, const N: usize>;
+            trait Clone;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        interner
+    }
+
+    #[test]
+    fn trait_impl_headers_round_trip() {
+        let interner = items();
+        for text in [
+            "impl A as Clone",
+            "impl<T0> B<T0> as PartialEq<B<A>>",
+            "impl<T0, const N1> [T0; N1] as Clone",
+            "impl fn(&drop [*mut u8]) -> B<A> as Clone",
+            "impl<T0> <T0 as Iterator>::Item as Clone",
+            "impl (A, (), !) as Clone",
+            "impl dyn PartialEq<A> as Clone",
+            "impl &mut *bool as Clone",
+            "impl NonZero<u32> as Clone",
+            "impl Arr<A, 3> as Clone",
+            "impl ? as Clone",
+        ] {
+            let (trait_id, pattern) = PatternSeq::parse_trait_impl(interner, text).unwrap();
+            let mut repr = String::new();
+            pattern
+                .format_as_trait_impl(interner, trait_id, &mut repr)
+                .unwrap();
+            assert_eq!(repr, text);
+        }
+    }
+
+    #[test]
+    fn generic_parameters_are_numbered_in_order_of_declaration() {
+        let interner = items();
+        let pattern = PatternSeq::parse_inherent_impl(interner, "impl<T, U> (U, T)").unwrap();
+        let mut repr = String::new();
+        pattern
+            .format_as_inherent_impl(interner, &mut repr)
+            .unwrap();
+        assert_eq!(repr, "impl<T0, T1> (T1, T0)");
+    }
+
+    #[test]
+    fn named_inference_variables_are_shared() {
+        let interner = items();
+        let pattern = PatternSeq::parse(interner, "B<T0>, ?x, ?, ?x, [A; ?]").unwrap();
+        let vars: Vec<_> = pattern
+            .iter()
+            .filter_map(|elem| elem.inference_var())
+            .collect();
+        assert_eq!(
+            vars,
+            [
+                InferenceVar(0),
+                InferenceVar(1),
+                InferenceVar(0),
+                InferenceVar(2)
+            ]
+        );
+        assert_eq!(pattern.last().unwrap().kind(), PatternKind::Const);
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        let interner = items();
+        for (text, position, message) in [
+            (
+                "impl A as Iterator<A>",
+                18,
+                "`Iterator` expects 0 generic argument(s)",
+            ),
+            (
+                "impl A as PartialEq",
+                19,
+                "`PartialEq` expects 1 generic argument(s)",
+            ),
+            (
+                "impl<T, T> T as Clone",
+                8,
+                "generic parameter `T` is declared twice",
+            ),
+            (
+                "impl A as Clone extra",
+                16,
+                "expected end of input, found `extra`",
+            ),
+            (
+                "impl <A as Clone>::Item as Clone",
+                19,
+                "trait `Clone` has no associated type `Item`",
+            ),
+            ("impl A as Copy", 10, "cannot find trait `Copy`"),
+            ("impl C as Clone", 5, "cannot find type `C`"),
+            ("impl Arr<A, x> as Clone", 12, "expected const, found type"),
+            (
+                "impl Arr<A, 340282366920938463463374607431768211456> as Clone",
+                12,
+                "const `340282366920938463463374607431768211456` doesn't fit into 128 bits",
+            ),
+            ("impl A $ as Clone", 7, "unexpected character `$`"),
+            (
+                "impl B<A, A> as Clone",
+                8,
+                "`B` expects 1 generic argument(s)",
+            ),
+            ("impl", 4, "expected type, found end of input"),
+        ] {
+            let error = PatternSeq::parse_trait_impl(interner, text).unwrap_err();
+            assert_eq!(
+                error,
+                ParseError {
+                    position,
+                    message: message.into(),
+                },
+                "text `{}`",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn where_clauses_reject_inference_variables() {
+        let interner = items();
+        let error = WhereClause::parse(interner, "impl<T> T as PartialEq<?>").unwrap_err();
+        assert_eq!(
+            error,
+            ParseError {
+                position: 23,
+                message: "where-clauses can't contain inference variables".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn placeholders_outside_impl_headers_are_not_mistaken_for_adts() {
+        let interner = items();
+        interner.new_adt("T0".into(), [].into());
+        assert_eq!(
+            PatternSeq::parse(interner, "B<T0>").unwrap_err(),
+            ParseError {
+                position: 2,
+                message: "`T0` may be either a placeholder or a type".into(),
+            }
+        );
+        let pattern = PatternSeq::parse(interner, "B<T1>").unwrap();
+        assert_eq!(
+            pattern[1],
+            PatternElement::TypePlaceholder(PlaceholderIndex(1))
+        );
+        // Generic parameters of impl headers shadow ADTs
+        let (_, pattern) =
+            PatternSeq::parse_trait_impl(interner, "impl<T0> B<T0> as Clone").unwrap();
+        assert_eq!(
+            pattern[1],
+            PatternElement::TypePlaceholder(PlaceholderIndex(0))
+        );
+    }
+}
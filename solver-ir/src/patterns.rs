@@ -1,18 +1,32 @@
 use crate::{
     interner::{Interner, TraitId},
-    primitives::{GenericArgType, PtrQual, RefQual, TypeId},
+    primitives::{ConstValue, GenericArgType, GenericsOwner, PtrQual, RefQual, TypeId},
+    unify::InferenceTable,
+};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    ops::Deref,
 };
-use std::{iter::FusedIterator, ops::Deref};
 
 /// TODO: write docs
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PatternKind {
     Type,
+    Const,
 }
 
 impl PartialEq<GenericArgType> for PatternKind {
     fn eq(&self, other: &GenericArgType) -> bool {
-        *self == PatternKind::from(*other)
+        match (self, other) {
+            (PatternKind::Type, GenericArgType::Type) => true,
+            (PatternKind::Const, GenericArgType::Const) => true,
+            (PatternKind::Type, GenericArgType::Const)
+            | (PatternKind::Const, GenericArgType::Type) => false,
+        }
     }
 }
 
@@ -20,10 +34,171 @@ impl From<GenericArgType> for PatternKind {
     fn from(value: GenericArgType) -> Self {
         match value {
             GenericArgType::Type => PatternKind::Type,
+            GenericArgType::Const => PatternKind::Const,
+        }
+    }
+}
+
+impl Display for PatternKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternKind::Type => write!(f, "type"),
+            PatternKind::Const => write!(f, "const"),
+        }
+    }
+}
+
+/// Reason why elements don't form a valid pattern.
+///
+/// Indices refer to elements of the checked slice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatternError {
+    /// Arguments of the type constructor at `index` don't cover exactly the `args_length`
+    /// elements following it, e.g. it has fewer arguments than its `arity`.
+    ArityMismatch { index: usize, arity: usize },
+    /// Pattern at `index` is of a different kind than its position requires.
+    KindMismatch {
+        index: usize,
+        expected: PatternKind,
+        found: PatternKind,
+    },
+    /// Elements end at `index`, but another pattern was expected there.
+    Truncated { index: usize },
+    /// Elements continue at `index` after the last expected pattern.
+    TrailingElements { index: usize },
+    /// Placeholder at `index` doesn't stand for any generic parameter.
+    UnknownPlaceholder { index: usize },
+    /// Placeholder or inference variable at `index` was already used with another kind.
+    MixedKinds { index: usize },
+    /// Arguments were finished at `index` without a type constructor to finish.
+    NoOpenConstructor { index: usize },
+    /// Trait impl pattern has a wrong number of generic arguments of the trait. `index` is the
+    /// end of the pattern if arguments are missing, or the first extra argument otherwise.
+    /// `found` doesn't count the implementor, and counts arguments up to the first extra one when
+    /// patterns are built one by one.
+    TraitArgCount {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Writing a valid pattern failed.
+    Format(std::fmt::Error),
+}
+
+impl From<std::fmt::Error> for PatternError {
+    fn from(value: std::fmt::Error) -> Self {
+        Self::Format(value)
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            PatternError::ArityMismatch { index, arity } => write!(
+                f,
+                "arguments of type constructor at {} don't match its arity {}",
+                index, arity
+            ),
+            PatternError::KindMismatch {
+                index,
+                expected,
+                found,
+            } => write!(f, "expected {} at {}, found {}", expected, index, found),
+            PatternError::Truncated { index } => {
+                write!(
+                    f,
+                    "pattern ends at {}, but more elements were expected",
+                    index
+                )
+            }
+            PatternError::TrailingElements { index } => {
+                write!(f, "unexpected elements after the pattern at {}", index)
+            }
+            PatternError::UnknownPlaceholder { index } => {
+                write!(f, "placeholder at {} isn't a generic parameter", index)
+            }
+            PatternError::MixedKinds { index } => write!(
+                f,
+                "pattern at {} was used as both a type and a const",
+                index
+            ),
+            PatternError::NoOpenConstructor { index } => {
+                write!(f, "no type constructor to finish at {}", index)
+            }
+            PatternError::TraitArgCount {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} trait generic arguments, found {} at {}",
+                expected, found, index
+            ),
+            PatternError::Format(_) => write!(f, "failed to write pattern"),
         }
     }
 }
 
+/// Returns kinds of arguments that a type constructor is applied to.
+fn arg_kinds<I: Interner>(interner: I, type_id: TypeId<I>) -> Vec<PatternKind> {
+    let generic_args = match type_id.generic_arg_types() {
+        Ok(args) => return args.iter().map(|&arg| arg.into()).collect(),
+        Err(owner) => owner,
+    };
+    let (self_ty, generic_args) = match generic_args {
+        GenericsOwner::Adt(adt_id) => {
+            let adt_data = interner.get_adt_by_id(adt_id);
+            (None, interner.adt_data(&adt_data).generic_args.clone())
+        }
+        GenericsOwner::AssocType(assoc_type_id) => {
+            let assoc_type_data = interner.get_assoc_type_by_id(assoc_type_id);
+            let trait_id = interner.assoc_type_data(&assoc_type_data).trait_id;
+            let trait_data = interner.get_trait_by_id(trait_id);
+            // Projections take the self type before generic arguments of the trait
+            (
+                Some(PatternKind::Type),
+                interner.trait_data(&trait_data).generic_args.clone(),
+            )
+        }
+        GenericsOwner::Trait(trait_id) => {
+            let trait_data = interner.get_trait_by_id(trait_id);
+            (None, interner.trait_data(&trait_data).generic_args.clone())
+        }
+    };
+    self_ty
+        .into_iter()
+        .chain(generic_args.iter().map(|&arg| arg.into()))
+        .collect()
+}
+
+/// Identity of an inference variable.
+///
+/// Every occurrence of the same `InferenceVar` in a pattern stands for the same yet unknown type or
+/// const. Type and const variables share indices, so the same index can't be used for both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InferenceVar(pub u32);
+
+impl InferenceVar {
+    /// Returns the index of the variable.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Identity of a placeholder.
+///
+/// Every occurrence of the same `PlaceholderIndex` in a pattern stands for the same opaque type or
+/// const. Type and const placeholders share indices, so the same index can't be used for both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlaceholderIndex(pub u32);
+
+impl PlaceholderIndex {
+    /// Returns the index of the placeholder.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 /// TODO: write docs
 #[derive(Clone, Copy, Debug)]
 pub enum PatternElement<I: Interner> {
@@ -33,17 +208,145 @@ pub enum PatternElement<I: Interner> {
         type_id: TypeId<I>,
     },
     /// Representation of opaque types (e.g. generics, opaque aliases, etc.)
-    TypePlaceholder,
+    TypePlaceholder(PlaceholderIndex),
     /// Representation of yet unknown types (i.e. inference variables)
-    InferredType,
+    InferredType(InferenceVar),
+    /// Representation of concrete const values
+    ConstValue(ConstValue),
+    /// Representation of opaque consts (e.g. const generics)
+    ConstPlaceholder(PlaceholderIndex),
+    /// Representation of yet unknown consts
+    InferredConst(InferenceVar),
+}
+
+impl<I: Interner> PartialEq for PatternElement<I> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::TypeConstructor {
+                    args_length: l_len,
+                    type_id: l_ty,
+                },
+                Self::TypeConstructor {
+                    args_length: r_len,
+                    type_id: r_ty,
+                },
+            ) => l_len == r_len && l_ty == r_ty,
+            (Self::TypePlaceholder(l_idx), Self::TypePlaceholder(r_idx)) => l_idx == r_idx,
+            (Self::InferredType(l_var), Self::InferredType(r_var)) => l_var == r_var,
+            (Self::ConstValue(l_val), Self::ConstValue(r_val)) => l_val == r_val,
+            (Self::ConstPlaceholder(l_idx), Self::ConstPlaceholder(r_idx)) => l_idx == r_idx,
+            (Self::InferredConst(l_var), Self::InferredConst(r_var)) => l_var == r_var,
+            _ => false,
+        }
+    }
+}
+
+impl<I: Interner> Eq for PatternElement<I> {}
+
+impl<I: Interner> Hash for PatternElement<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::TypeConstructor {
+                args_length,
+                type_id,
+            } => {
+                args_length.hash(state);
+                type_id.hash(state);
+            }
+            Self::TypePlaceholder(idx) | Self::ConstPlaceholder(idx) => idx.hash(state),
+            Self::InferredType(var) | Self::InferredConst(var) => var.hash(state),
+            Self::ConstValue(value) => value.hash(state),
+        }
+    }
+}
+
+impl<I: Interner> PartialOrd for PatternElement<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders elements by their variant in declaration order and then by payload.
+impl<I: Interner> Ord for PatternElement<I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (
+                Self::TypeConstructor {
+                    args_length: l_len,
+                    type_id: l_ty,
+                },
+                Self::TypeConstructor {
+                    args_length: r_len,
+                    type_id: r_ty,
+                },
+            ) => l_ty.cmp(r_ty).then(l_len.cmp(r_len)),
+            (Self::TypePlaceholder(l_idx), Self::TypePlaceholder(r_idx)) => l_idx.cmp(r_idx),
+            (Self::InferredType(l_var), Self::InferredType(r_var)) => l_var.cmp(r_var),
+            (Self::ConstValue(l_val), Self::ConstValue(r_val)) => l_val.cmp(r_val),
+            (Self::ConstPlaceholder(l_idx), Self::ConstPlaceholder(r_idx)) => l_idx.cmp(r_idx),
+            (Self::InferredConst(l_var), Self::InferredConst(r_var)) => l_var.cmp(r_var),
+            _ => self.variant_index().cmp(&other.variant_index()),
+        }
+    }
 }
 
 impl<I: Interner> PatternElement<I> {
+    /// Returns position of the variant of `self` in the declaration of `PatternElement`.
+    fn variant_index(&self) -> u8 {
+        match self {
+            Self::TypeConstructor { .. } => 0,
+            Self::TypePlaceholder(_) => 1,
+            Self::InferredType(_) => 2,
+            Self::ConstValue(_) => 3,
+            Self::ConstPlaceholder(_) => 4,
+            Self::InferredConst(_) => 5,
+        }
+    }
+
+    /// Creates placeholder element of provided kind.
+    pub fn new_placeholder(kind: PatternKind, idx: PlaceholderIndex) -> Self {
+        match kind {
+            PatternKind::Type => PatternElement::TypePlaceholder(idx),
+            PatternKind::Const => PatternElement::ConstPlaceholder(idx),
+        }
+    }
+
+    /// Creates inference variable element of provided kind.
+    pub fn new_inference_var(kind: PatternKind, var: InferenceVar) -> Self {
+        match kind {
+            PatternKind::Type => PatternElement::InferredType(var),
+            PatternKind::Const => PatternElement::InferredConst(var),
+        }
+    }
+
     /// Returns whether given `PatternElement` represents an entity that needs to be inferred.
     pub fn is_inference_var(&self) -> bool {
-        match self {
-            PatternElement::TypeConstructor { .. } | PatternElement::TypePlaceholder => false,
-            PatternElement::InferredType => true,
+        self.inference_var().is_some()
+    }
+
+    /// Returns inference variable represented by given `PatternElement`, if it is one.
+    pub fn inference_var(&self) -> Option<InferenceVar> {
+        match *self {
+            PatternElement::InferredType(var) | PatternElement::InferredConst(var) => Some(var),
+            PatternElement::TypeConstructor { .. }
+            | PatternElement::TypePlaceholder(_)
+            | PatternElement::ConstValue(_)
+            | PatternElement::ConstPlaceholder(_) => None,
+        }
+    }
+
+    /// Returns placeholder represented by given `PatternElement`, if it is one.
+    pub fn placeholder(&self) -> Option<PlaceholderIndex> {
+        match *self {
+            PatternElement::TypePlaceholder(idx) | PatternElement::ConstPlaceholder(idx) => {
+                Some(idx)
+            }
+            PatternElement::TypeConstructor { .. }
+            | PatternElement::InferredType(_)
+            | PatternElement::ConstValue(_)
+            | PatternElement::InferredConst(_) => None,
         }
     }
 
@@ -51,8 +354,11 @@ impl<I: Interner> PatternElement<I> {
     pub fn kind(&self) -> PatternKind {
         match self {
             PatternElement::TypeConstructor { .. }
-            | PatternElement::TypePlaceholder
-            | PatternElement::InferredType => PatternKind::Type,
+            | PatternElement::TypePlaceholder(_)
+            | PatternElement::InferredType(_) => PatternKind::Type,
+            PatternElement::ConstValue(_)
+            | PatternElement::ConstPlaceholder(_)
+            | PatternElement::InferredConst(_) => PatternKind::Const,
         }
     }
 }
@@ -78,9 +384,7 @@ impl<'a, I: Interner> Iterator for PatternSeqIter<'a, I> {
     type Item = &'a Pattern<I>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(pat) = self.0 else {
-            return None;
-        };
+        let pat = self.0?;
         let (head, tail) = pat.split_first();
         self.0 = tail;
         Some(head)
@@ -119,9 +423,7 @@ impl<'a, I: Interner> Iterator for ExactPatternSeqIter<'a, I> {
     type Item = &'a ExactPattern<I>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Some(pat) = self.0 else {
-            return None;
-        };
+        let pat = self.0?;
         let (head, tail) = pat.split_first();
         self.0 = tail;
         Some(head)
@@ -150,50 +452,96 @@ impl<I: Interner> PatternSeq<I> {
         unsafe { &*pat }
     }
 
+    /// Creates new boxed `PatternSeq` from provided boxed slice without any checks.
+    ///
+    /// # Safety
+    /// `pattern` must be a valid `PatternSeq`.
+    pub unsafe fn new_boxed_unchecked(pattern: Box<[PatternElement<I>]>) -> Box<Self> {
+        let pat = Box::into_raw(pattern) as *mut Self;
+        // Safe because `pat` was obtained from `Box::into_raw`
+        unsafe { Box::from_raw(pat) }
+    }
+
     /// Creates new `PatternSeq` by checking that provided slice forms a sequence of valid
     /// `Pattern`s.
-    pub fn new(interner: I, pattern: &[PatternElement<I>]) -> Option<&Self> {
+    pub fn new(interner: I, pattern: &[PatternElement<I>]) -> Result<&Self, PatternError> {
         if pattern.is_empty() {
-            return None;
+            return Err(PatternError::Truncated { index: 0 });
         }
-        let mut pat = pattern;
-        while !pat.is_empty() {
-            pat = Pattern::new_any(interner, pat)?.1;
+        let mut next = 0;
+        while next != pattern.len() {
+            next = Pattern::check_at(interner, pattern, next, None)?;
         }
         // Safe because we just checked that `pattern` is valid `PatternSeq`
-        Some(unsafe { Self::new_unchecked(pattern) })
+        Ok(unsafe { Self::new_unchecked(pattern) })
     }
 
-    /// TODO: write docs
+    /// Creates new `PatternSeq` by checking that provided slice is an implementor type followed
+    /// by generic arguments of `trait_id`.
     pub fn new_trait_impl(
         interner: I,
         pattern: &[PatternElement<I>],
         trait_id: TraitId<I>,
-    ) -> Option<&Self> {
-        let seq = Self::new(interner, pattern)?;
+    ) -> Result<&Self, PatternError> {
         let trait_data = interner.get_trait_by_id(trait_id);
         let generic_args = &*interner.trait_data(&trait_data).generic_args;
-        if !seq.has_same_structure_as(
-            std::iter::once(GenericArgType::Type).chain(generic_args.iter().copied()),
-        ) {
-            None
-        } else {
-            Some(seq)
+        let mut next = Pattern::check_at(interner, pattern, 0, Some(PatternKind::Type))?;
+        for (found, &arg) in generic_args.iter().enumerate() {
+            if next == pattern.len() {
+                return Err(PatternError::TraitArgCount {
+                    index: next,
+                    expected: generic_args.len(),
+                    found,
+                });
+            }
+            next = Pattern::check_at(interner, pattern, next, Some(arg.into()))?;
+        }
+        if next != pattern.len() {
+            let extra_start = next;
+            let mut found = generic_args.len();
+            while next != pattern.len() {
+                next = Pattern::check_at(interner, pattern, next, None)?;
+                found += 1;
+            }
+            return Err(PatternError::TraitArgCount {
+                index: extra_start,
+                expected: generic_args.len(),
+                found,
+            });
         }
+        // Safe because we just checked that `pattern` is valid `PatternSeq`
+        Ok(unsafe { Self::new_unchecked(pattern) })
+    }
+
+    /// Creates new `PatternSeq` by checking that provided slice is a single implementor type of an
+    /// inherent impl.
+    pub fn new_inherent_impl(
+        interner: I,
+        pattern: &[PatternElement<I>],
+    ) -> Result<&Self, PatternError> {
+        let end = Pattern::check_at(interner, pattern, 0, Some(PatternKind::Type))?;
+        if end != pattern.len() {
+            return Err(PatternError::TrailingElements { index: end });
+        }
+        // Safe because we just checked that `pattern` is valid `PatternSeq`
+        Ok(unsafe { Self::new_unchecked(pattern) })
     }
 
     /// TODO: write docs
     pub fn boxed(&self) -> Box<Self> {
-        let pat = Box::into_raw(Box::<[_]>::from(&self[..])) as *mut Self;
-        // Safe because `pat` was obtained from `Box::into_raw`
-        unsafe { Box::from_raw(pat) }
+        // Safe because `self` is a valid `PatternSeq`
+        unsafe { Self::new_boxed_unchecked(self[..].into()) }
     }
 
     /// TODO: write docs
     pub fn split_first(&self) -> (&Pattern<I>, Option<&Self>) {
-        let first_len = match self.get(0).expect("`PatternSeq` should be non-empty") {
+        let first_len = match self.first().expect("`PatternSeq` should be non-empty") {
             PatternElement::TypeConstructor { args_length, .. } => 1 + args_length,
-            PatternElement::TypePlaceholder | PatternElement::InferredType => 1,
+            PatternElement::TypePlaceholder(_)
+            | PatternElement::InferredType(_)
+            | PatternElement::ConstValue(_)
+            | PatternElement::ConstPlaceholder(_)
+            | PatternElement::InferredConst(_) => 1,
         };
         (
             // Safe because ...
@@ -228,23 +576,40 @@ impl<I: Interner> PatternSeq<I> {
         }
     }
 
-    /// TODO: write docs
+    /// Checks whether inference variables of `self` can be bound so that it becomes an instance
+    /// of `pattern`.
+    ///
+    /// Placeholders of `pattern` stand for arbitrary types, while placeholders of `self` are
+    /// opaque and match only placeholders of `pattern`.
     pub fn matches(&self, pattern: &ExactPatternSeq<I>) -> bool {
-        let (mut head, mut maybe_tail) = self.split_first();
-        let (mut head_pat, mut maybe_tail_pat) = pattern.split_first();
-        loop {
-            if !head.matches(head_pat) {
-                break false;
-            }
-            match (maybe_tail, maybe_tail_pat) {
-                (Some(tail), Some(tail_pat)) => {
-                    (head, maybe_tail) = tail.split_first();
-                    (head_pat, maybe_tail_pat) = tail_pat.split_first();
-                }
-                (None, None) => break true,
-                (Some(_), None) | (None, Some(_)) => break false,
+        self.match_bindings(pattern).is_some()
+    }
+
+    /// Returns placeholders occurring in `self` ordered by their indices.
+    pub fn placeholders(&self) -> Vec<PlaceholderIndex> {
+        let mut indices: Vec<_> = self
+            .iter()
+            .filter_map(|elem| Some(elem.placeholder()?.0))
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.into_iter().map(PlaceholderIndex).collect()
+    }
+
+    fn format_impl_generics(&self, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        let placeholders = self.placeholders();
+        for (i, &idx) in placeholders.iter().enumerate() {
+            write!(f, "{}", if i == 0 { "<" } else { ", " })?;
+            if self.contains(&PatternElement::ConstPlaceholder(idx)) {
+                write!(f, "const N{}", idx.0)?;
+            } else {
+                write!(f, "T{}", idx.0)?;
             }
         }
+        if !placeholders.is_empty() {
+            write!(f, ">")?;
+        }
+        Ok(())
     }
 
     /// TODO: write docs
@@ -259,82 +624,44 @@ impl<I: Interner> PatternSeq<I> {
         Ok(())
     }
 
-    /// TODO: write docs
+    /// Writes `self` as header of an inherent impl, e.g. `impl<T0> B<T0>`.
     ///
-    /// # Panics
-    /// This panics if `self` doesn't form a valid pattern for inherent impl.
+    /// Fails if `self` isn't a single type pattern, see `PatternSeq::new_inherent_impl`.
     pub fn format_as_inherent_impl(
         &self,
         interner: I,
         f: &mut dyn std::fmt::Write,
-    ) -> std::fmt::Result {
-        write!(f, "impl ")?;
-        let (implementor, tail) = self.split_first();
-        if implementor.kind() != PatternKind::Type {
-            panic!(
-                "implementor of an inherent impl must be a type\nimplementor: {:?}",
-                implementor
-            );
-        }
-        if let Some(_) = tail {
-            panic!(
-                "pattern is too long for an inherent impl\npattern: {:?}",
-                self
-            );
-        }
-        implementor.format(interner, f)
+    ) -> Result<(), PatternError> {
+        Self::new_inherent_impl(interner, self)?;
+        write!(f, "impl")?;
+        self.format_impl_generics(f)?;
+        write!(f, " ")?;
+        self.split_first().0.format(interner, f)?;
+        Ok(())
     }
 
-    /// TODO: write docs
+    /// Writes `self` as header of an impl of `trait_id`, e.g. `impl<T0> B<T0> as Clone`.
     ///
-    /// # Panics
-    /// This panics if `self` doesn't form a valid pattern for trait impl for provided `trait_id`.
+    /// Fails if `self` doesn't have the layout of a trait impl pattern for `trait_id`, see
+    /// `PatternSeq::new_trait_impl`.
     pub fn format_as_trait_impl(
         &self,
         interner: I,
         trait_id: TraitId<I>,
         f: &mut dyn std::fmt::Write,
-    ) -> std::fmt::Result {
-        write!(f, "impl ")?;
+    ) -> Result<(), PatternError> {
+        Self::new_trait_impl(interner, self, trait_id)?;
+        write!(f, "impl")?;
+        self.format_impl_generics(f)?;
+        write!(f, " ")?;
         let (implementor, trait_args) = self.split_first();
-        if implementor.kind() != PatternKind::Type {
-            panic!(
-                "implementor of a trait impl must be a type\nimplementor: {:?}",
-                implementor
-            );
-        }
         implementor.format(interner, f)?;
-        write!(f, " as ")?;
         let trait_data = interner.get_trait_by_id(trait_id);
-        let (trait_name, trait_generics) = {
-            let trait_data = interner.trait_data(&trait_data);
-            (&*trait_data.name, &*trait_data.generic_args)
-        };
-        write!(f, "{}", trait_name)?;
-        if trait_generics.len() != 0 {
-            let Some(trait_args) = trait_args else {
-                panic!(
-                    "trait `{}` expected generic arguments, but none were provided\n\
-                     trait args: {:?}",
-                    trait_name, trait_generics
-                );
-            };
-            if !trait_args.has_same_structure_as(trait_generics.iter().copied()) {
-                panic!(
-                    "invalid generic arguments were provided for trait `{}`\n\
-                     trait args: {:?}\nprovided args: {:?}",
-                    trait_name, trait_generics, trait_args
-                );
-            }
+        write!(f, " as {}", interner.trait_data(&trait_data).name)?;
+        if let Some(trait_args) = trait_args {
             write!(f, "<")?;
             trait_args.format(interner, f)?;
             write!(f, ">")?;
-        } else if let Some(trait_args) = trait_args {
-            panic!(
-                "trait `{}` didn't expect generic arguments, but some were provided\n\
-                 provided args: {:?}",
-                trait_name, trait_args
-            );
         }
         Ok(())
     }
@@ -378,23 +705,74 @@ impl<I: Interner> ExactPatternSeq<I> {
         )
     }
 
-    /// TODO: write docs
+    /// Checks whether no type is an instance of both `self` and `other`.
+    ///
+    /// Placeholders of both sequences stand for arbitrary types, and are independent between
+    /// `self` and `other`.
     pub fn disjoint_with(&self, other: &Self) -> bool {
-        let (mut self_head, mut maybe_self_tail) = self.split_first();
-        let (mut other_head, mut maybe_other_tail) = other.split_first();
-        loop {
-            if self_head.disjoint_with(other_head) {
-                break true;
-            }
-            match (maybe_self_tail, maybe_other_tail) {
-                (Some(self_tail), Some(other_tail)) => {
-                    (self_head, maybe_self_tail) = self_tail.split_first();
-                    (other_head, maybe_other_tail) = other_tail.split_first();
-                }
-                (None, None) => break false,
-                (Some(_), None) | (None, Some(_)) => break true,
+        let mut table = InferenceTable::new();
+        let (this, _) = table.instantiate(self);
+        let (other, _) = table.instantiate(other);
+        table.unify(&this, &other).is_err()
+    }
+
+    /// Returns the most general pattern whose instances are instances of both `self` and
+    /// `other`, or `None` if they are disjoint.
+    ///
+    /// E.g. the intersection of `B<A>, T0` and `B<T0>, C` is `B<A>, C`. Placeholders of both
+    /// sequences are independent, and placeholders of the intersection are numbered in order of
+    /// their first occurrence.
+    pub fn intersection(&self, other: &Self) -> Option<Box<Self>> {
+        let mut table = InferenceTable::new();
+        let (this, _) = table.instantiate(self);
+        let (other, _) = table.instantiate(other);
+        table.unify(&this, &other).ok()?;
+        // Variables left unbound can be any type or const, so they become placeholders
+        let mut placeholders = Vec::new();
+        let intersection = table.resolve_with(&this, &mut |elem| match elem.inference_var() {
+            Some(var) => {
+                let idx = placeholders
+                    .iter()
+                    .position(|&placeholder| placeholder == var)
+                    .unwrap_or_else(|| {
+                        placeholders.push(var);
+                        placeholders.len() - 1
+                    });
+                PatternElement::new_placeholder(elem.kind(), PlaceholderIndex(idx as u32))
             }
+            None => elem,
+        });
+        // Safe because every inference variable was replaced by a placeholder
+        Some(unsafe { Self::new_unchecked(&intersection) }.boxed())
+    }
+
+    /// Returns the most specific pattern that matches every sequence of `patterns`, i.e. their
+    /// least general generalization, or `None` if there are no sequences or they consist of
+    /// different numbers or kinds of patterns.
+    ///
+    /// E.g. `B<A>`, `B<u8>` and `B<&A>` generalize to `B<T0>`. Positions where the sequences
+    /// disagree become placeholders, and the same disagreement becomes the same placeholder, so
+    /// `(A, A)` and `(C, C)` generalize to `(T0, T0)`. Placeholders of `patterns` stand for
+    /// arbitrary types and consts, so they always disagree with other patterns.
+    pub fn generalize<'a>(patterns: impl IntoIterator<Item = &'a Self>) -> Option<Box<Self>>
+    where
+        I: 'a,
+    {
+        let seqs: Vec<&PatternSeq<I>> = patterns.into_iter().map(|seq| &**seq).collect();
+        let (first, rest) = seqs.split_first()?;
+        let kinds: Vec<_> = first.into_iter().map(|pat| pat.kind()).collect();
+        if rest
+            .iter()
+            .any(|seq| !seq.has_same_structure_as(kinds.iter().copied()))
+        {
+            return None;
         }
+        let mut generalization = Vec::new();
+        let mut placeholders = HashMap::new();
+        generalize_seqs_into(&seqs, &mut placeholders, &mut generalization);
+        // Safe because the generalization has the structure of `patterns`, which don't contain
+        // inference variables, with some of their subpatterns replaced by placeholders
+        Some(unsafe { Self::new_unchecked(&generalization) }.boxed())
     }
 }
 
@@ -435,60 +813,116 @@ impl<I: Interner> Pattern<I> {
         unsafe { &*pat }
     }
 
-    /// TODO: write docs
+    /// Creates new `Pattern` of any kind from the beginning of `pattern` and returns it together
+    /// with the rest of `pattern`.
     pub fn new_any(
         interner: I,
         pattern: &[PatternElement<I>],
-    ) -> Option<(&Self, &[PatternElement<I>])> {
-        match pattern.first()? {
-            PatternElement::TypeConstructor { .. }
-            | PatternElement::TypePlaceholder
-            | PatternElement::InferredType => Self::new_type(interner, pattern),
-        }
+    ) -> Result<(&Self, &[PatternElement<I>]), PatternError> {
+        let end = Self::check_at(interner, pattern, 0, None)?;
+        // Safe because we just checked that `pattern[0..end]` is valid `Pattern`
+        Ok((
+            unsafe { Self::new_unchecked(&pattern[0..end]) },
+            &pattern[end..],
+        ))
     }
 
-    /// TODO: write docs
+    /// Creates new `Pattern` of provided kind from the beginning of `pattern` and returns it
+    /// together with the rest of `pattern`.
     pub fn new_of_kind(
         interner: I,
         pattern: &[PatternElement<I>],
         kind: PatternKind,
-    ) -> Option<(&Self, &[PatternElement<I>])> {
-        match kind {
-            PatternKind::Type => Self::new_type(interner, pattern),
+    ) -> Result<(&Self, &[PatternElement<I>]), PatternError> {
+        let end = Self::check_at(interner, pattern, 0, Some(kind))?;
+        // Safe because we just checked that `pattern[0..end]` is valid `Pattern`
+        Ok((
+            unsafe { Self::new_unchecked(&pattern[0..end]) },
+            &pattern[end..],
+        ))
+    }
+
+    /// Creates new const `Pattern` from the beginning of `pattern` and returns it together with
+    /// the rest of `pattern`.
+    pub fn new_const(
+        pattern: &[PatternElement<I>],
+    ) -> Result<(&Self, &[PatternElement<I>]), PatternError> {
+        let first = pattern
+            .first()
+            .ok_or(PatternError::Truncated { index: 0 })?;
+        if first.kind() != PatternKind::Const {
+            return Err(PatternError::KindMismatch {
+                index: 0,
+                expected: PatternKind::Const,
+                found: first.kind(),
+            });
         }
+        Ok((
+            // Safe because every const pattern consists of a single const element
+            unsafe { Self::new_unchecked(&pattern[0..1]) },
+            &pattern[1..],
+        ))
     }
 
-    /// TODO: write docs
+    /// Creates new type `Pattern` from the beginning of `pattern` and returns it together with
+    /// the rest of `pattern`.
     pub fn new_type(
         interner: I,
         pattern: &[PatternElement<I>],
-    ) -> Option<(&Self, &[PatternElement<I>])> {
-        let pat_len = match pattern.first()? {
-            &PatternElement::TypeConstructor {
-                args_length,
-                type_id: ty,
-            } => {
-                let adt_data: I::InternedAdtData;
-                let generic_args = match ty.generic_arg_types() {
-                    Ok(args) => args,
-                    Err(adt_id) => {
-                        adt_data = interner.get_adt_by_id(adt_id);
-                        &interner.adt_data(&adt_data).generic_args
-                    }
-                };
-                let mut args_pat = pattern.get(1..args_length + 1)?;
-                for &arg in generic_args {
-                    args_pat = Self::new_of_kind(interner, args_pat, arg.into())?.1;
-                }
-                args_length + 1
-            }
-            PatternElement::TypePlaceholder | PatternElement::InferredType => 1,
+    ) -> Result<(&Self, &[PatternElement<I>]), PatternError> {
+        Self::new_of_kind(interner, pattern, PatternKind::Type)
+    }
+
+    /// Checks that a pattern of provided kind (or of any kind if `kind` is `None`) starts at
+    /// `elems[start]`, and returns the index where it ends.
+    fn check_at(
+        interner: I,
+        elems: &[PatternElement<I>],
+        start: usize,
+        kind: Option<PatternKind>,
+    ) -> Result<usize, PatternError> {
+        let first = elems
+            .get(start)
+            .ok_or(PatternError::Truncated { index: elems.len() })?;
+        if let Some(kind) = kind
+            && first.kind() != kind
+        {
+            return Err(PatternError::KindMismatch {
+                index: start,
+                expected: kind,
+                found: first.kind(),
+            });
+        }
+        let &PatternElement::TypeConstructor {
+            args_length,
+            type_id,
+        } = first
+        else {
+            return Ok(start + 1);
         };
-        Some((
-            // Safe because we just checked that `pattern[0..pat_len]` is valid `Pattern`
-            unsafe { Self::new_unchecked(&pattern[0..pat_len]) },
-            &pattern[pat_len..],
-        ))
+        let end = start + 1 + args_length;
+        if end > elems.len() {
+            return Err(PatternError::Truncated { index: elems.len() });
+        }
+        let kinds = arg_kinds(interner, type_id);
+        let arity_mismatch = PatternError::ArityMismatch {
+            index: start,
+            arity: kinds.len(),
+        };
+        // Arguments are checked within `args_length` elements, so running out of them means that
+        // the constructor has fewer arguments than its arity
+        let args = &elems[..end];
+        let mut next = start + 1;
+        for kind in kinds {
+            next = match Self::check_at(interner, args, next, Some(kind)) {
+                Err(PatternError::Truncated { .. }) => return Err(arity_mismatch),
+                result => result?,
+            };
+        }
+        if next != end {
+            return Err(arity_mismatch);
+        }
+        Ok(end)
     }
 
     /// Returns the kind of provided `Pattern`.
@@ -507,9 +941,9 @@ impl<I: Interner> Pattern<I> {
     /// TODO: write docs
     pub fn args(&self) -> Option<&PatternSeq<I>> {
         if self.len() != 1 {
-            // NOTE: this safety will need adjustments after introduction of const patterns
             // Safety:
             //   * `self` can't be a trivial pattern because `self.len() > 1`
+            //   * const patterns are always trivial
             //   * therefore `self` must be a type constructor with non-empty arguments
             Some(unsafe { PatternSeq::new_unchecked(&self[1..]) })
         } else {
@@ -517,39 +951,19 @@ impl<I: Interner> Pattern<I> {
         }
     }
 
-    /// TODO: write docs
+    /// Checks whether inference variables of `self` can be bound so that it becomes an instance
+    /// of `pattern`.
     pub fn matches(&self, pattern: &ExactPattern<I>) -> bool {
-        match (self.first(), pattern.first()) {
-            (
-                PatternElement::TypeConstructor {
-                    type_id: first_ty, ..
-                },
-                PatternElement::TypeConstructor {
-                    type_id: second_ty, ..
-                },
-            ) => {
-                if first_ty != second_ty {
-                    return false;
-                }
-                match (self.args(), pattern.args()) {
-                    (Some(args), Some(args_pat)) => args.matches(args_pat),
-                    (None, None) => true,
-                    (Some(_), None) | (None, Some(_)) => unreachable!(),
-                }
-            }
-            (PatternElement::TypeConstructor { .. }, PatternElement::TypePlaceholder)
-            | (PatternElement::TypePlaceholder, PatternElement::TypePlaceholder) => true,
-            (PatternElement::TypePlaceholder, PatternElement::TypeConstructor { .. }) => false,
-            (PatternElement::InferredType, pat) => pat.kind() == PatternKind::Type,
-            (_, PatternElement::InferredType) => unreachable!(),
-        }
+        self.0.matches(pattern.as_seq())
     }
 
     /// TODO: write docs
     pub fn format(&self, interner: I, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
         match self.first() {
-            PatternElement::TypePlaceholder => write!(f, "_"),
-            PatternElement::InferredType => write!(f, "?"),
+            PatternElement::TypePlaceholder(idx) => write!(f, "T{}", idx.0),
+            PatternElement::InferredType(_) | PatternElement::InferredConst(_) => write!(f, "?"),
+            PatternElement::ConstValue(value) => write!(f, "{}", value),
+            PatternElement::ConstPlaceholder(idx) => write!(f, "N{}", idx.0),
             PatternElement::TypeConstructor {
                 type_id: TypeId::Scalar(ty),
                 ..
@@ -578,6 +992,78 @@ impl<I: Interner> Pattern<I> {
                 arg.format(interner, f)?;
                 write!(f, "]")
             }
+            &PatternElement::TypeConstructor {
+                type_id: TypeId::Array,
+                ..
+            } => {
+                // Safe because `[T; N]` has element type and length arguments, and `self` is a
+                // valid `Pattern`
+                let args = unsafe { PatternSeq::new_unchecked(&self[1..]) };
+                let (element, len) = args.split_first();
+                let len = len.expect("array should have a length").split_first().0;
+                write!(f, "[")?;
+                element.format(interner, f)?;
+                write!(f, "; ")?;
+                len.format(interner, f)?;
+                write!(f, "]")
+            }
+            &PatternElement::TypeConstructor {
+                type_id: TypeId::Tuple(arity),
+                ..
+            } => {
+                write!(f, "(")?;
+                if let Some(elements) = self.args() {
+                    elements.format(interner, f)?;
+                }
+                // Single element tuples need a trailing comma to differ from parenthesized types
+                if arity == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            &PatternElement::TypeConstructor {
+                type_id: TypeId::FnPtr(_),
+                ..
+            } => {
+                // Safe because `fn` has at least the return type argument and `self` is a valid
+                // `Pattern`
+                let args = unsafe { PatternSeq::new_unchecked(&self[1..]) };
+                let args: Vec<_> = args.into_iter().collect();
+                let (ret, params) = args.split_last().expect("`fn` should have a return type");
+                write!(f, "fn(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    param.format(interner, f)?;
+                }
+                write!(f, ")")?;
+                let returns_unit = matches!(
+                    ret.first(),
+                    PatternElement::TypeConstructor {
+                        type_id: TypeId::Tuple(0),
+                        ..
+                    }
+                );
+                if !returns_unit {
+                    write!(f, " -> ")?;
+                    ret.format(interner, f)?;
+                }
+                Ok(())
+            }
+            &PatternElement::TypeConstructor {
+                type_id: TypeId::Dyn(trait_id),
+                ..
+            } => {
+                let trait_data = interner.get_trait_by_id(trait_id);
+                write!(f, "dyn {}", interner.trait_data(&trait_data).name)?;
+                if let Some(trait_args) = self.args() {
+                    write!(f, "<")?;
+                    trait_args.format(interner, f)?;
+                    write!(f, ">")?;
+                }
+                Ok(())
+            }
             &PatternElement::TypeConstructor {
                 type_id: TypeId::Ref(qual),
                 ..
@@ -611,6 +1097,27 @@ impl<I: Interner> Pattern<I> {
                 }
                 Ok(())
             }
+            &PatternElement::TypeConstructor {
+                type_id: TypeId::Projection(assoc_type_id),
+                ..
+            } => {
+                // Safe because projection arguments form a valid `PatternSeq` starting with the
+                // self type
+                let args = unsafe { PatternSeq::new_unchecked(&self[1..]) };
+                let (self_ty, trait_args) = args.split_first();
+                let assoc_type_data = interner.get_assoc_type_by_id(assoc_type_id);
+                let assoc_type_data = interner.assoc_type_data(&assoc_type_data);
+                let trait_data = interner.get_trait_by_id(assoc_type_data.trait_id);
+                write!(f, "<")?;
+                self_ty.format(interner, f)?;
+                write!(f, " as {}", interner.trait_data(&trait_data).name)?;
+                if let Some(trait_args) = trait_args {
+                    write!(f, "<")?;
+                    trait_args.format(interner, f)?;
+                    write!(f, ">")?;
+                }
+                write!(f, ">::{}", assoc_type_data.name)
+            }
         }
     }
 }
@@ -639,10 +1146,10 @@ impl<I: Interner> ExactPattern<I> {
     /// TODO: write docs
     pub fn args(&self) -> Option<&ExactPatternSeq<I>> {
         if self.len() != 1 {
-            // NOTE: this safety will need adjustments after introduction of const patterns
             // Safety:
             //   * any subpattern of `self` is `ExactPatternSeq`, because `self` is `ExactPattern`
             //   * `self` can't be a trivial pattern because `self.len() > 1`
+            //   * const patterns are always trivial
             //   * therefore `self` must be a type constructor with non-empty arguments
             Some(unsafe { ExactPatternSeq::new_unchecked(&self[1..]) })
         } else {
@@ -650,30 +1157,690 @@ impl<I: Interner> ExactPattern<I> {
         }
     }
 
-    /// TODO: write docs
+    /// Returns `self` as a single element `ExactPatternSeq`.
+    pub fn as_seq(&self) -> &ExactPatternSeq<I> {
+        // Safe because `self` is a valid `ExactPattern`
+        unsafe { ExactPatternSeq::new_unchecked(self) }
+    }
+
+    /// Checks whether no type is an instance of both `self` and `other`.
     pub fn disjoint_with(&self, other: &Self) -> bool {
-        match (self.first(), other.first()) {
+        self.as_seq().disjoint_with(other.as_seq())
+    }
+
+    /// Returns the most general pattern matched by both `self` and `other`, see
+    /// `ExactPatternSeq::intersection`.
+    pub fn intersection(&self, other: &Self) -> Option<Box<ExactPatternSeq<I>>> {
+        self.as_seq().intersection(other.as_seq())
+    }
+}
+
+/// Writes generalization of sequences `seqs`, which have the same structure, to `out`.
+///
+/// `placeholders` maps every disagreement, i.e. patterns at the same position of every sequence,
+/// to the placeholder that replaces it.
+fn generalize_seqs_into<'a, I: Interner>(
+    seqs: &[&'a PatternSeq<I>],
+    placeholders: &mut HashMap<Vec<&'a Pattern<I>>, PatternElement<I>>,
+    out: &mut Vec<PatternElement<I>>,
+) {
+    let mut iters: Vec<_> = seqs.iter().map(|seq| seq.into_iter()).collect();
+    loop {
+        let column: Option<Vec<_>> = iters.iter_mut().map(Iterator::next).collect();
+        let Some(column) = column else {
+            break;
+        };
+        generalize_into(column, placeholders, out);
+    }
+}
+
+/// Writes generalization of `column`, patterns at the same position of every sequence, to `out`.
+fn generalize_into<'a, I: Interner>(
+    column: Vec<&'a Pattern<I>>,
+    placeholders: &mut HashMap<Vec<&'a Pattern<I>>, PatternElement<I>>,
+    out: &mut Vec<PatternElement<I>>,
+) {
+    let first = *column[0].first();
+    let agree = match first {
+        PatternElement::TypeConstructor { type_id, .. } => column.iter().all(|pat| {
+            matches!(
+                *pat.first(),
+                PatternElement::TypeConstructor { type_id: other, .. } if other == type_id
+            )
+        }),
+        PatternElement::ConstValue(_) => column.iter().all(|pat| *pat.first() == first),
+        PatternElement::TypePlaceholder(_)
+        | PatternElement::InferredType(_)
+        | PatternElement::ConstPlaceholder(_)
+        | PatternElement::InferredConst(_) => false,
+    };
+    if !agree {
+        let idx = PlaceholderIndex(placeholders.len() as u32);
+        let kind = column[0].kind();
+        let placeholder = *placeholders
+            .entry(column)
+            .or_insert_with(|| PatternElement::new_placeholder(kind, idx));
+        out.push(placeholder);
+        return;
+    }
+    let PatternElement::TypeConstructor { type_id, .. } = first else {
+        out.push(first);
+        return;
+    };
+    let start = out.len();
+    out.push(PatternElement::TypeConstructor {
+        args_length: 0,
+        type_id,
+    });
+    // Constructors with the same `type_id` have the same number and kinds of arguments
+    let args: Option<Vec<_>> = column.iter().map(|pat| pat.args()).collect();
+    if let Some(args) = args {
+        generalize_seqs_into(&args, placeholders, out);
+    }
+    out[start] = PatternElement::TypeConstructor {
+        args_length: out.len() - start - 1,
+        type_id,
+    };
+}
+
+/// Implements structural `PartialEq`, `Eq`, `Hash`, `PartialOrd` and `Ord` of a pattern type by
+/// comparing its elements, so patterns are ordered lexicographically by their prefix encoding.
+macro_rules! impl_elements_eq_ord {
+    ($name:ident) => {
+        impl<I: Interner> PartialEq for $name<I> {
+            fn eq(&self, other: &Self) -> bool {
+                self[..] == other[..]
+            }
+        }
+
+        impl<I: Interner> Eq for $name<I> {}
+
+        impl<I: Interner> Hash for $name<I> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self[..].hash(state)
+            }
+        }
+
+        impl<I: Interner> PartialOrd for $name<I> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<I: Interner> Ord for $name<I> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self[..].cmp(&other[..])
+            }
+        }
+    };
+}
+
+impl_elements_eq_ord!(PatternSeq);
+impl_elements_eq_ord!(ExactPatternSeq);
+impl_elements_eq_ord!(Pattern);
+impl_elements_eq_ord!(ExactPattern);
+
+/// Type constructor whose arguments are being added to a `PatternBuilder`.
+#[derive(Debug)]
+struct OpenCtor {
+    /// Index of the constructor element.
+    index: usize,
+    arg_kinds: Vec<PatternKind>,
+    args: usize,
+}
+
+/// Builder of an owned `PatternSeq`, which computes lengths of constructor arguments and checks
+/// them against generic parameters of the referenced items.
+///
+/// Patterns are added in prefix order: `ctor` starts a type constructor, which is closed by
+/// `finish_args` after all of its arguments were added. Placeholders and inference variables get
+/// the kind that their position requires, so e.g. `placeholder` in the length of an array is a
+/// const placeholder.
+///
+/// E.g. `B<T0>, ?` is built by `ctor(TypeId::Adt(b))`, `placeholder(PlaceholderIndex(0))`,
+/// `finish_args()` and `infer(InferenceVar(0))`. Methods return errors as soon as a pattern doesn't
+/// fit its position, and leave the builder unchanged in that case. The same placeholder or
+/// inference variable can't be added both as a type and as a const.
+#[derive(Debug)]
+pub struct PatternBuilder<I: Interner> {
+    interner: I,
+    elems: Vec<PatternElement<I>>,
+    /// Kinds of top level patterns, or `None` if any number of patterns is allowed.
+    top_level_kinds: Option<Vec<PatternKind>>,
+    top_level_count: usize,
+    /// Constructors whose arguments are being added, innermost last.
+    open: Vec<OpenCtor>,
+    /// Kinds of added placeholders and inference variables.
+    placeholder_kinds: HashMap<PlaceholderIndex, PatternKind>,
+    inference_var_kinds: HashMap<InferenceVar, PatternKind>,
+}
+
+impl<I: Interner> PatternBuilder<I> {
+    /// Creates builder of a sequence of any number of patterns.
+    ///
+    /// Top level placeholders and inference variables are types.
+    pub fn new(interner: I) -> Self {
+        Self {
+            interner,
+            elems: Vec::new(),
+            top_level_kinds: None,
+            top_level_count: 0,
+            open: Vec::new(),
+            placeholder_kinds: HashMap::new(),
+            inference_var_kinds: HashMap::new(),
+        }
+    }
+
+    /// Creates builder of a trait impl pattern for `trait_id`, which is an implementor type
+    /// followed by generic arguments of the trait.
+    pub fn for_trait_impl(interner: I, trait_id: TraitId<I>) -> Self {
+        let trait_data = interner.get_trait_by_id(trait_id);
+        let kinds = std::iter::once(PatternKind::Type)
+            .chain(
+                interner
+                    .trait_data(&trait_data)
+                    .generic_args
+                    .iter()
+                    .map(|&arg| arg.into()),
+            )
+            .collect();
+        Self {
+            top_level_kinds: Some(kinds),
+            ..Self::new(interner)
+        }
+    }
+
+    /// Returns the kind required at the position of the next pattern, or `None` if any kind is
+    /// allowed there.
+    fn expected_kind(&self) -> Result<Option<PatternKind>, PatternError> {
+        if let Some(open) = self.open.last() {
+            return match open.arg_kinds.get(open.args) {
+                Some(&kind) => Ok(Some(kind)),
+                None => Err(PatternError::ArityMismatch {
+                    index: open.index,
+                    arity: open.arg_kinds.len(),
+                }),
+            };
+        }
+        match &self.top_level_kinds {
+            Some(kinds) => match kinds.get(self.top_level_count) {
+                Some(&kind) => Ok(Some(kind)),
+                // Added trait arguments don't include the implementor, but include the rejected
+                // pattern, so they are as many as added top level patterns
+                None => Err(PatternError::TraitArgCount {
+                    index: self.elems.len(),
+                    expected: kinds.len() - 1,
+                    found: self.top_level_count,
+                }),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Adds `elem` as the next pattern. The builder is left unchanged on errors.
+    fn push(&mut self, elem: PatternElement<I>) -> Result<&mut Self, PatternError> {
+        if let Some(expected) = self.expected_kind()?
+            && elem.kind() != expected
+        {
+            return Err(PatternError::KindMismatch {
+                index: self.elems.len(),
+                expected,
+                found: elem.kind(),
+            });
+        }
+        match self.open.last_mut() {
+            Some(open) => open.args += 1,
+            None => self.top_level_count += 1,
+        }
+        self.elems.push(elem);
+        Ok(self)
+    }
+
+    /// Starts type constructor `type_id`, whose arguments are added until `finish_args`.
+    pub fn ctor(&mut self, type_id: TypeId<I>) -> Result<&mut Self, PatternError> {
+        let index = self.elems.len();
+        // Length of arguments is written by `finish_args`
+        self.push(PatternElement::TypeConstructor {
+            args_length: 0,
+            type_id,
+        })?;
+        self.open.push(OpenCtor {
+            index,
+            arg_kinds: arg_kinds(self.interner, type_id),
+            args: 0,
+        });
+        Ok(self)
+    }
+
+    /// Finishes arguments of the innermost constructor started by `ctor`.
+    pub fn finish_args(&mut self) -> Result<&mut Self, PatternError> {
+        let Some(open) = self.open.last() else {
+            return Err(PatternError::NoOpenConstructor {
+                index: self.elems.len(),
+            });
+        };
+        if open.args != open.arg_kinds.len() {
+            return Err(PatternError::ArityMismatch {
+                index: open.index,
+                arity: open.arg_kinds.len(),
+            });
+        }
+        let index = open.index;
+        self.open.pop();
+        let PatternElement::TypeConstructor { type_id, .. } = self.elems[index] else {
+            unreachable!("open constructor should be a type constructor");
+        };
+        self.elems[index] = PatternElement::TypeConstructor {
+            args_length: self.elems.len() - index - 1,
+            type_id,
+        };
+        Ok(self)
+    }
+
+    /// Adds placeholder of the kind required at its position.
+    pub fn placeholder(&mut self, idx: PlaceholderIndex) -> Result<&mut Self, PatternError> {
+        let kind = self.expected_kind()?.unwrap_or(PatternKind::Type);
+        let index = self.elems.len();
+        Self::check_kind(&mut self.placeholder_kinds, idx, kind, index)?;
+        self.push(PatternElement::new_placeholder(kind, idx))
+    }
+
+    /// Adds inference variable of the kind required at its position.
+    pub fn infer(&mut self, var: InferenceVar) -> Result<&mut Self, PatternError> {
+        let kind = self.expected_kind()?.unwrap_or(PatternKind::Type);
+        let index = self.elems.len();
+        Self::check_kind(&mut self.inference_var_kinds, var, kind, index)?;
+        self.push(PatternElement::new_inference_var(kind, var))
+    }
+
+    /// Adds const value.
+    pub fn constant(&mut self, value: ConstValue) -> Result<&mut Self, PatternError> {
+        self.push(PatternElement::ConstValue(value))
+    }
+
+    /// Records that `key` is used as `kind` at `index`, unless it was used with another kind.
+    ///
+    /// Only called right before a push that can't fail, so the builder stays unchanged on errors.
+    fn check_kind<K: Eq + Hash>(
+        kinds: &mut HashMap<K, PatternKind>,
+        key: K,
+        kind: PatternKind,
+        index: usize,
+    ) -> Result<(), PatternError> {
+        match *kinds.entry(key).or_insert(kind) {
+            used if used == kind => Ok(()),
+            _ => Err(PatternError::MixedKinds { index }),
+        }
+    }
+
+    /// Returns the built sequence.
+    ///
+    /// Fails if a constructor wasn't finished or if patterns are missing.
+    pub fn finish(self) -> Result<Box<PatternSeq<I>>, PatternError> {
+        if self.elems.is_empty() || !self.open.is_empty() {
+            return Err(PatternError::Truncated {
+                index: self.elems.len(),
+            });
+        }
+        if let Some(kinds) = &self.top_level_kinds
+            && self.top_level_count != kinds.len()
+        {
+            // The implementor isn't an argument of the trait
+            return Err(PatternError::TraitArgCount {
+                index: self.elems.len(),
+                expected: kinds.len() - 1,
+                found: self.top_level_count - 1,
+            });
+        }
+        // Safe because every pattern was checked against its position when it was added and every
+        // constructor has exactly its arity of arguments
+        Ok(unsafe { PatternSeq::new_boxed_unchecked(self.elems.into()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        add_items,
+        interner::AdtId,
+        primitives::{Scalar, UIntType},
+        test_interner::TestInterner,
+    };
+
+    type Interner = &'static TestInterner;
+
+    /// Creates interner with ADTs `A`, `B<T>` and `C`, and with trait `PartialEq<Rhs>`.
+    #[allow(non_snake_case)]
+    fn items() -> (Interner, AdtId<Interner>, TraitId<Interner>) {
+        let interner = TestInterner::new();
+        let (_A, B, _C, PartialEq) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct C;
+            trait PartialEq<Rhs>;
+        });
+        (interner, B, PartialEq)
+    }
+
+    fn exact(interner: Interner, text: &str) -> Box<ExactPatternSeq<Interner>> {
+        ExactPatternSeq::new(&PatternSeq::parse(interner, text).unwrap())
+            .unwrap()
+            .boxed()
+    }
+
+    fn type_placeholder(idx: u32) -> PatternElement<Interner> {
+        PatternElement::TypePlaceholder(PlaceholderIndex(idx))
+    }
+
+    #[test]
+    fn new_rejects_malformed_constructors() {
+        let (interner, b, _) = items();
+        let ctor = |type_id, args_length| PatternElement::TypeConstructor {
+            args_length,
+            type_id,
+        };
+        for (elems, expected) in [
+            (vec![], PatternError::Truncated { index: 0 }),
             (
-                PatternElement::TypeConstructor {
-                    type_id: self_ty, ..
-                },
-                PatternElement::TypeConstructor {
-                    type_id: other_ty, ..
+                vec![ctor(TypeId::Adt(b), 0)],
+                PatternError::ArityMismatch { index: 0, arity: 1 },
+            ),
+            (
+                vec![ctor(TypeId::Adt(b), 2), type_placeholder(0)],
+                PatternError::Truncated { index: 2 },
+            ),
+            (
+                vec![
+                    ctor(TypeId::Adt(b), 2),
+                    type_placeholder(0),
+                    type_placeholder(1),
+                ],
+                PatternError::ArityMismatch { index: 0, arity: 1 },
+            ),
+            (
+                vec![
+                    ctor(TypeId::Array, 2),
+                    type_placeholder(0),
+                    type_placeholder(1),
+                ],
+                PatternError::KindMismatch {
+                    index: 2,
+                    expected: PatternKind::Const,
+                    found: PatternKind::Type,
                 },
-            ) => {
-                if self_ty != other_ty {
-                    return true;
-                }
-                match (self.args(), other.args()) {
-                    (Some(self_args), Some(other_args)) => self_args.disjoint_with(other_args),
-                    (None, None) => false,
-                    (Some(_), None) | (None, Some(_)) => unreachable!(),
-                }
+            ),
+        ] {
+            assert_eq!(PatternSeq::new(interner, &elems).unwrap_err(), expected);
+        }
+    }
+
+    #[test]
+    fn impl_headers_reject_wrong_layouts() {
+        let (interner, _, partial_eq) = items();
+        let elems = [
+            PatternElement::ConstValue(ConstValue(1)),
+            type_placeholder(0),
+        ];
+        assert_eq!(
+            PatternSeq::new_trait_impl(interner, &elems, partial_eq).unwrap_err(),
+            PatternError::KindMismatch {
+                index: 0,
+                expected: PatternKind::Type,
+                found: PatternKind::Const,
+            }
+        );
+        let elems = [type_placeholder(0), type_placeholder(1)];
+        let error = PatternSeq::new_inherent_impl(interner, &elems).unwrap_err();
+        assert_eq!(error, PatternError::TrailingElements { index: 1 });
+        let pattern = PatternSeq::new(interner, &elems).unwrap();
+        let mut repr = String::new();
+        assert_eq!(
+            pattern.format_as_inherent_impl(interner, &mut repr),
+            Err(error)
+        );
+        let error = PatternSeq::new_trait_impl(interner, &elems[..1], partial_eq).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected 1 trait generic arguments, found 0 at 1"
+        );
+    }
+
+    #[test]
+    fn builder_counts_extra_trait_args_like_new_trait_impl() {
+        let (interner, _, partial_eq) = items();
+        let elems = [
+            type_placeholder(0),
+            type_placeholder(1),
+            type_placeholder(2),
+        ];
+        let error = PatternSeq::new_trait_impl(interner, &elems, partial_eq).unwrap_err();
+        let mut builder = PatternBuilder::for_trait_impl(interner, partial_eq);
+        builder
+            .placeholder(PlaceholderIndex(0))
+            .unwrap()
+            .placeholder(PlaceholderIndex(1))
+            .unwrap();
+        let expected = PatternError::TraitArgCount {
+            index: 2,
+            expected: 1,
+            found: 2,
+        };
+        assert_eq!(error, expected);
+        assert_eq!(
+            builder.placeholder(PlaceholderIndex(2)).unwrap_err(),
+            expected
+        );
+    }
+
+    #[test]
+    fn builder_counts_missing_trait_args_like_new_trait_impl() {
+        let (interner, _, partial_eq) = items();
+        let elems = [type_placeholder(0)];
+        let error = PatternSeq::new_trait_impl(interner, &elems, partial_eq).unwrap_err();
+        let mut builder = PatternBuilder::for_trait_impl(interner, partial_eq);
+        builder.placeholder(PlaceholderIndex(0)).unwrap();
+        let expected = PatternError::TraitArgCount {
+            index: 1,
+            expected: 1,
+            found: 0,
+        };
+        assert_eq!(error, expected);
+        assert_eq!(builder.finish().unwrap_err(), expected);
+    }
+
+    #[test]
+    fn builder_computes_lengths_of_constructor_arguments() {
+        let (interner, b, _) = items();
+        let mut builder = PatternBuilder::new(interner);
+        builder
+            .ctor(TypeId::Adt(b))
+            .unwrap()
+            .ctor(TypeId::Tuple(2))
+            .unwrap()
+            .placeholder(PlaceholderIndex(0))
+            .unwrap()
+            .ctor(TypeId::Array)
+            .unwrap()
+            .ctor(TypeId::Scalar(Scalar::UInt(UIntType::u8)))
+            .unwrap()
+            .finish_args()
+            .unwrap()
+            .constant(ConstValue(3))
+            .unwrap()
+            .finish_args()
+            .unwrap()
+            .finish_args()
+            .unwrap()
+            .finish_args()
+            .unwrap()
+            .infer(InferenceVar(0))
+            .unwrap();
+        let built = builder.finish().unwrap();
+        assert_eq!(
+            built[..],
+            PatternSeq::parse(interner, "B<(T0, [u8; 3])>, ?").unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn builder_rejects_unbalanced_constructors() {
+        let (interner, b, _) = items();
+        let mut builder = PatternBuilder::new(interner);
+        assert_eq!(
+            builder.finish_args().unwrap_err(),
+            PatternError::NoOpenConstructor { index: 0 }
+        );
+        builder.ctor(TypeId::Adt(b)).unwrap();
+        assert_eq!(
+            builder.finish_args().unwrap_err(),
+            PatternError::ArityMismatch { index: 0, arity: 1 }
+        );
+        builder.placeholder(PlaceholderIndex(0)).unwrap();
+        assert_eq!(
+            builder.placeholder(PlaceholderIndex(1)).unwrap_err(),
+            PatternError::ArityMismatch { index: 0, arity: 1 }
+        );
+        builder.finish_args().unwrap();
+        assert_eq!(
+            builder.finish_args().unwrap_err(),
+            PatternError::NoOpenConstructor { index: 2 }
+        );
+        builder.ctor(TypeId::Adt(b)).unwrap();
+        assert_eq!(
+            builder.finish().unwrap_err(),
+            PatternError::Truncated { index: 3 }
+        );
+    }
+
+    #[test]
+    fn builder_rejects_kind_mismatch() {
+        let (interner, b, _) = items();
+        let mut builder = PatternBuilder::new(interner);
+        builder.ctor(TypeId::Adt(b)).unwrap();
+        assert_eq!(
+            builder.constant(ConstValue(3)).unwrap_err(),
+            PatternError::KindMismatch {
+                index: 1,
+                expected: PatternKind::Type,
+                found: PatternKind::Const,
+            }
+        );
+    }
+
+    #[test]
+    fn builder_rejects_mixed_kinds() {
+        let (interner, _, _) = items();
+        let mut builder = PatternBuilder::new(interner);
+        builder
+            .ctor(TypeId::Array)
+            .unwrap()
+            .placeholder(PlaceholderIndex(0))
+            .unwrap();
+        assert_eq!(
+            builder.placeholder(PlaceholderIndex(0)).unwrap_err(),
+            PatternError::MixedKinds { index: 2 }
+        );
+        builder
+            .placeholder(PlaceholderIndex(1))
+            .unwrap()
+            .finish_args()
+            .unwrap()
+            .infer(InferenceVar(0))
+            .unwrap()
+            .ctor(TypeId::Array)
+            .unwrap()
+            .infer(InferenceVar(1))
+            .unwrap();
+        assert_eq!(
+            builder.infer(InferenceVar(0)).unwrap_err(),
+            PatternError::MixedKinds { index: 6 }
+        );
+        builder
+            .infer(InferenceVar(2))
+            .unwrap()
+            .finish_args()
+            .unwrap();
+        let built = builder.finish().unwrap();
+        assert_eq!(
+            built.placeholders(),
+            [PlaceholderIndex(0), PlaceholderIndex(1)]
+        );
+        assert!(built.contains(&PatternElement::ConstPlaceholder(PlaceholderIndex(1))));
+    }
+
+    #[test]
+    fn intersection_is_symmetric_common_instance() {
+        let (interner, _, _) = items();
+        for (a, b, expected) in [
+            ("B<A>, T0", "B<T0>, C", Some("B<A>, C")),
+            // `B<A>` is contained in `B<T0>`
+            ("B<A>", "B<T0>", Some("B<A>")),
+            ("(T0, T0)", "(A, T0)", Some("(A, A)")),
+            ("T0, T1", "T1, T0", Some("T0, T1")),
+            ("[T0; N1]", "[A; 3]", Some("[A; 3]")),
+            ("B<A>", "B<C>", None),
+            ("[A; 3]", "[A; 4]", None),
+            ("(T0, B<T0>)", "(B<T1>, T1)", None),
+        ] {
+            let (a, b) = (exact(interner, a), exact(interner, b));
+            let expected = expected.map(|text| exact(interner, text));
+            assert_eq!(a.intersection(&b), expected, "{:?} and {:?}", a, b);
+            assert_eq!(b.intersection(&a), expected, "{:?} and {:?}", b, a);
+            assert_eq!(a.disjoint_with(&b), expected.is_none());
+            if let Some(intersection) = expected {
+                assert!(intersection.matches(&a) && intersection.matches(&b));
+            }
+        }
+    }
+
+    #[test]
+    fn generalization_is_least_general() {
+        let (interner, _, _) = items();
+        for (patterns, expected) in [
+            (&["B<A>", "B<u8>", "B<&A>"][..], Some("B<T0>")),
+            (&["(A, A)", "(C, C)"], Some("(T0, T0)")),
+            (&["(A, C)", "(C, A)"], Some("(T0, T1)")),
+            (&["(A, C)", "(A, A)"], Some("(A, T0)")),
+            (&["[A; 3]", "[A; 4]"], Some("[A; N0]")),
+            (&["B<A>, 3", "B<C>, 3"], Some("B<T0>, 3")),
+            (&["B<A>"], Some("B<A>")),
+            (&["A", "A, A"], None),
+            (&["A", "3"], None),
+            (&[], None),
+        ] {
+            let patterns: Vec<_> = patterns.iter().map(|text| exact(interner, text)).collect();
+            let general = ExactPatternSeq::generalize(patterns.iter().map(|pat| &**pat));
+            assert_eq!(general, expected.map(|text| exact(interner, text)));
+            if let Some(general) = general {
+                assert!(patterns.iter().all(|pat| pat.matches(&general)));
+            }
+        }
+    }
+
+    #[test]
+    fn patterns_are_ordered_lexicographically_by_elements() {
+        let (interner, _, _) = items();
+        let texts = [
+            "A", "A, A", "B<A>", "B<C>", "B<B<A>>", "C", "u8", "()", "T0", "T0, A", "T1",
+        ];
+        let patterns: Vec<_> = texts.iter().map(|text| exact(interner, text)).collect();
+        for (i, l) in patterns.iter().enumerate() {
+            for (j, r) in patterns.iter().enumerate() {
+                assert_eq!(
+                    l.cmp(r),
+                    l[..].cmp(&r[..]),
+                    "`{}` and `{}`",
+                    texts[i],
+                    texts[j]
+                );
+                // `texts` are sorted: constructors by type and then by length of arguments, and
+                // prefixes before longer sequences
+                assert_eq!(l.cmp(r), i.cmp(&j), "`{}` and `{}`", texts[i], texts[j]);
             }
-            (PatternElement::TypeConstructor { .. }, PatternElement::TypePlaceholder)
-            | (PatternElement::TypePlaceholder, PatternElement::TypeConstructor { .. })
-            | (PatternElement::TypePlaceholder, PatternElement::TypePlaceholder) => false,
-            (PatternElement::InferredType, _) | (_, PatternElement::InferredType) => unreachable!(),
         }
+        let mut sorted: Vec<_> = patterns.iter().rev().collect();
+        sorted.sort();
+        assert!(sorted.into_iter().eq(&patterns));
     }
 }
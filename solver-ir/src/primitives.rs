@@ -1,11 +1,34 @@
 //! TODO: write docs
 
-use super::interner::{AdtId, Interner, Substitution, Type};
-use std::fmt::Display;
+use super::{
+    interner::{AdtId, AssocTypeId, Interner, Substitution, TraitId, Type},
+    patterns::{
+        ExactPatternSeq, PatternElement, PatternError, PatternKind, PatternSeq, PlaceholderIndex,
+    },
+};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum GenericArgType {
     Type,
+    Const,
+}
+
+/// Value of a const generic argument.
+///
+/// Values are compared bitwise, so they are untyped: `3usize` and `3u8` are the same value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConstValue(pub u128);
+
+impl Display for ConstValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
 #[derive(Debug)]
@@ -16,13 +39,126 @@ pub struct AdtData {
 }
 
 #[derive(Debug)]
-pub struct TraitData {
+pub struct TraitData<I: Interner> {
     pub name: Box<str>,
     pub generic_args: Box<[GenericArgType]>,
-    // TODO: add bounds / where clauses & associated types
+    /// Bounds that every implementor of the trait must satisfy, e.g. `Self: Eq` of
+    /// `trait Ord: Eq`. `Self` is placeholder 0 and generic arguments of the trait are
+    /// placeholders `1..=generic_args.len()`.
+    pub supertraits: Box<[WhereClause<I>]>,
+    // TODO: add where clauses
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Associated type declared by a trait, e.g. `Item` of `trait Iterator { type Item; }`.
+#[derive(Debug)]
+pub struct AssocTypeData<I: Interner> {
+    pub name: Box<str>,
+    /// Trait that declares the associated type.
+    pub trait_id: TraitId<I>,
+}
+
+/// Value of an associated type in a trait impl, e.g. `type Item = T;`.
+#[derive(Debug)]
+pub struct AssocTypeValue<I: Interner> {
+    pub assoc_type_id: AssocTypeId<I>,
+    /// Single pattern, whose placeholders are the same as placeholders of the impl header.
+    pub value: Box<ExactPatternSeq<I>>,
+}
+
+/// Bound of the form `Type: Trait<Args>`.
+#[derive(Debug)]
+pub struct WhereClause<I: Interner> {
+    pub trait_id: TraitId<I>,
+    /// Bounded type followed by generic arguments of the trait.
+    pub pattern: Box<ExactPatternSeq<I>>,
+}
+
+impl<I: Interner> Clone for WhereClause<I> {
+    fn clone(&self) -> Self {
+        Self {
+            trait_id: self.trait_id,
+            pattern: self.pattern.boxed(),
+        }
+    }
+}
+
+impl<I: Interner> PartialEq for WhereClause<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.trait_id == other.trait_id && self.pattern == other.pattern
+    }
+}
+
+impl<I: Interner> WhereClause<I> {
+    /// Creates `Self: Trait` bound of a trait declaration, where `Self` is placeholder 0.
+    ///
+    /// Fails if `trait_id` expects generic arguments.
+    pub fn new_self_bound(interner: I, trait_id: TraitId<I>) -> Result<Self, PatternError> {
+        let pattern = [PatternElement::TypePlaceholder(PlaceholderIndex(0))];
+        let pattern = PatternSeq::new_trait_impl(interner, &pattern, trait_id)?;
+        Ok(Self {
+            trait_id,
+            // Safe because the pattern is a single placeholder
+            pattern: unsafe { ExactPatternSeq::new_unchecked(pattern) }.boxed(),
+        })
+    }
+
+    /// Checks that `self` is a valid bound of a trait with generic parameters `generic_args`, i.e.
+    /// that its placeholders are `Self` or generic parameters of the trait, see
+    /// `TraitData::supertraits`.
+    pub fn check_trait_bound(
+        &self,
+        interner: I,
+        generic_args: &[GenericArgType],
+    ) -> Result<(), PatternError> {
+        PatternSeq::new_trait_impl(interner, &self.pattern, self.trait_id)?;
+        for (index, elem) in self.pattern.iter().enumerate() {
+            let Some(idx) = elem.placeholder() else {
+                continue;
+            };
+            let expected = match idx.index() {
+                0 => PatternKind::Type,
+                param => generic_args
+                    .get(param - 1)
+                    .map(|&arg| arg.into())
+                    .ok_or(PatternError::UnknownPlaceholder { index })?,
+            };
+            if elem.kind() != expected {
+                return Err(PatternError::KindMismatch {
+                    index,
+                    expected,
+                    found: elem.kind(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ImplData<I: Interner> {
+    /// Implemented trait or `None` for inherent impls.
+    pub trait_id: Option<TraitId<I>>,
+    /// Implementor followed by generic arguments of the implemented trait.
+    pub pattern: Box<ExactPatternSeq<I>>,
+    /// Bounds that must hold for the impl to apply. Placeholders of bounds are the same as
+    /// placeholders of `pattern`.
+    pub where_clauses: Box<[WhereClause<I>]>,
+    /// Values of associated types of the implemented trait.
+    pub assoc_types: Box<[AssocTypeValue<I>]>,
+    // TODO: add associated functions and constants
+}
+
+impl<I: Interner> ImplData<I> {
+    /// Returns value of provided associated type, if the impl defines it.
+    pub fn assoc_type_value(&self, assoc_type_id: AssocTypeId<I>) -> Option<&ExactPatternSeq<I>> {
+        self.assoc_types
+            .iter()
+            .find(|assoc_type| assoc_type.assoc_type_id == assoc_type_id)
+            .map(|assoc_type| &*assoc_type.value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)]
 pub enum Scalar {
     bool,
@@ -46,7 +182,7 @@ impl Display for Scalar {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)]
 pub enum IntType {
     i8,
@@ -68,7 +204,7 @@ impl Display for IntType {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)]
 pub enum UIntType {
     u8,
@@ -90,7 +226,7 @@ impl Display for UIntType {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[allow(non_camel_case_types)]
 pub enum FloatType {
     f16,
@@ -108,7 +244,7 @@ impl Display for FloatType {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum RefQual {
     Mut,
     Drop,
@@ -124,7 +260,7 @@ impl RefQual {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum PtrQual {
     Mut,
 }
@@ -147,22 +283,70 @@ pub enum TypeId<I: Interner> {
     // that `NonZero<T>` can be `{integer}` as well.
     NonZero,
     Slice,
-    // Array,
+    /// Array `[T; N]`, which is applied to the element type followed by the length.
+    Array,
+    /// Tuple of provided arity, e.g. `(A, B)` has arity 2 and `()` has arity 0.
+    Tuple(usize),
     Ref(Option<RefQual>),
     Ptr(Option<PtrQual>),
     Never,
-    // TODO: add function pointers and `dyn Trait`
+    /// Function pointer with provided number of parameters, which is applied to the parameter
+    /// types followed by the return type.
+    FnPtr(usize),
+    /// Trait object `dyn Trait<Args>`, which is applied to generic arguments of the trait.
+    Dyn(TraitId<I>),
+    /// Associated type projection `<Type as Trait<Args>>::Name`, which is applied to the self
+    /// type followed by generic arguments of the trait.
+    Projection(AssocTypeId<I>),
+}
+
+/// Item whose generic arguments are declared in the interner.
+#[derive(Clone, Copy, Debug)]
+pub enum GenericsOwner<I: Interner> {
+    Adt(AdtId<I>),
+    /// Associated type, whose generic arguments are the self type followed by generic arguments
+    /// of its trait.
+    AssocType(AssocTypeId<I>),
+    /// Trait of a trait object, whose generic arguments are generic arguments of the trait.
+    Trait(TraitId<I>),
 }
 
 impl<I: Interner> TypeId<I> {
-    /// Returns generic argument types if `self` is one of built-in types and `AdtId` otherwise.
-    pub fn generic_arg_types<'a>(self) -> Result<&'a [GenericArgType], AdtId<I>> {
+    /// Returns position of the variant of `self` in the declaration of `TypeId`.
+    fn variant_index(&self) -> u8 {
         match self {
-            TypeId::Adt(id) => Err(id),
+            Self::Adt(_) => 0,
+            Self::Scalar(_) => 1,
+            Self::NonZero => 2,
+            Self::Slice => 3,
+            Self::Array => 4,
+            Self::Tuple(_) => 5,
+            Self::Ref(_) => 6,
+            Self::Ptr(_) => 7,
+            Self::Never => 8,
+            Self::FnPtr(_) => 9,
+            Self::Dyn(_) => 10,
+            Self::Projection(_) => 11,
+        }
+    }
+
+    /// Returns generic argument types if `self` is one of built-in types and the item declaring
+    /// them otherwise.
+    pub fn generic_arg_types(self) -> Result<Cow<'static, [GenericArgType]>, GenericsOwner<I>> {
+        match self {
+            TypeId::Adt(id) => Err(GenericsOwner::Adt(id)),
+            TypeId::Projection(id) => Err(GenericsOwner::AssocType(id)),
+            TypeId::Dyn(id) => Err(GenericsOwner::Trait(id)),
             TypeId::NonZero | TypeId::Slice | TypeId::Ref(_) | TypeId::Ptr(_) => {
-                Ok(&[GenericArgType::Type])
+                Ok(Cow::Borrowed(&[GenericArgType::Type]))
             }
-            TypeId::Scalar(_) | TypeId::Never => Ok(&[]),
+            TypeId::Array => Ok(Cow::Borrowed(&[
+                GenericArgType::Type,
+                GenericArgType::Const,
+            ])),
+            TypeId::Tuple(arity) => Ok(Cow::Owned(vec![GenericArgType::Type; arity])),
+            TypeId::FnPtr(arity) => Ok(Cow::Owned(vec![GenericArgType::Type; arity + 1])),
+            TypeId::Scalar(_) | TypeId::Never => Ok(Cow::Borrowed(&[])),
         }
     }
 }
@@ -174,11 +358,57 @@ impl<I: Interner> PartialEq for TypeId<I> {
             (Self::Scalar(l0), Self::Scalar(r0)) => l0 == r0,
             (Self::Ref(l0), Self::Ref(r0)) => l0 == r0,
             (Self::Ptr(l0), Self::Ptr(r0)) => l0 == r0,
+            (Self::Projection(l0), Self::Projection(r0)) => l0 == r0,
+            (Self::Tuple(l0), Self::Tuple(r0)) => l0 == r0,
+            (Self::FnPtr(l0), Self::FnPtr(r0)) => l0 == r0,
+            (Self::Dyn(l0), Self::Dyn(r0)) => l0 == r0,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
 }
 
+impl<I: Interner> Eq for TypeId<I> {}
+
+impl<I: Interner> Hash for TypeId<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Variants without payload are equal by discriminant only
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Adt(id) => id.hash(state),
+            Self::Scalar(scalar) => scalar.hash(state),
+            Self::Ref(qual) => qual.hash(state),
+            Self::Ptr(qual) => qual.hash(state),
+            Self::Projection(id) => id.hash(state),
+            Self::Tuple(arity) | Self::FnPtr(arity) => arity.hash(state),
+            Self::Dyn(id) => id.hash(state),
+            Self::NonZero | Self::Slice | Self::Array | Self::Never => {}
+        }
+    }
+}
+
+impl<I: Interner> PartialOrd for TypeId<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders type constructors by their variant in declaration order and then by payload.
+impl<I: Interner> Ord for TypeId<I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Adt(l0), Self::Adt(r0)) => l0.cmp(r0),
+            (Self::Scalar(l0), Self::Scalar(r0)) => l0.cmp(r0),
+            (Self::Ref(l0), Self::Ref(r0)) => l0.cmp(r0),
+            (Self::Ptr(l0), Self::Ptr(r0)) => l0.cmp(r0),
+            (Self::Projection(l0), Self::Projection(r0)) => l0.cmp(r0),
+            (Self::Tuple(l0), Self::Tuple(r0)) => l0.cmp(r0),
+            (Self::FnPtr(l0), Self::FnPtr(r0)) => l0.cmp(r0),
+            (Self::Dyn(l0), Self::Dyn(r0)) => l0.cmp(r0),
+            _ => self.variant_index().cmp(&other.variant_index()),
+        }
+    }
+}
+
 /// TODO: write docs
 #[derive(Debug)]
 pub enum TypeKind<I: Interner> {
@@ -186,9 +416,58 @@ pub enum TypeKind<I: Interner> {
     Scalar(Scalar),
     NonZero(Type<I>),
     Slice(Type<I>),
+    Array(Type<I>, ConstValue),
+    Tuple(Substitution<I>),
     Ref(Option<RefQual>, Type<I>),
     Ptr(Option<PtrQual>, Type<I>),
     Never,
+    /// Substitution contains parameter types followed by the return type.
+    FnPtr(Substitution<I>),
+    Dyn(TraitId<I>, Substitution<I>),
+    /// Substitution starts with the self type, followed by generic arguments of the trait.
+    Projection(AssocTypeId<I>, Substitution<I>),
+}
+
+// Equality and hashing of types are shallow: nested types and substitutions are compared by their
+// interned handles. `derive` can't be used, because it would require the interner to implement
+// these traits as well.
+impl<I: Interner> PartialEq for TypeKind<I> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Adt(l0, l1), Self::Adt(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Scalar(l0), Self::Scalar(r0)) => l0 == r0,
+            (Self::NonZero(l0), Self::NonZero(r0)) => l0 == r0,
+            (Self::Slice(l0), Self::Slice(r0)) => l0 == r0,
+            (Self::Array(l0, l1), Self::Array(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Tuple(l0), Self::Tuple(r0)) => l0 == r0,
+            (Self::Ref(l0, l1), Self::Ref(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Ptr(l0, l1), Self::Ptr(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::FnPtr(l0), Self::FnPtr(r0)) => l0 == r0,
+            (Self::Dyn(l0, l1), Self::Dyn(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Projection(l0, l1), Self::Projection(r0, r1)) => l0 == r0 && l1 == r1,
+            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
+        }
+    }
+}
+
+impl<I: Interner> Eq for TypeKind<I> {}
+
+impl<I: Interner> Hash for TypeKind<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Adt(adt_id, subst) => (adt_id, subst).hash(state),
+            Self::Scalar(scalar) => scalar.hash(state),
+            Self::NonZero(ty) | Self::Slice(ty) => ty.hash(state),
+            Self::Array(ty, len) => (ty, len).hash(state),
+            Self::Tuple(subst) | Self::FnPtr(subst) => subst.hash(state),
+            Self::Ref(qual, ty) => (qual, ty).hash(state),
+            Self::Ptr(qual, ty) => (qual, ty).hash(state),
+            Self::Never => {}
+            Self::Dyn(trait_id, subst) => (trait_id, subst).hash(state),
+            Self::Projection(assoc_type_id, subst) => (assoc_type_id, subst).hash(state),
+        }
+    }
 }
 
 /// TODO: write docs
@@ -197,8 +476,130 @@ pub struct TypeData<I: Interner> {
     pub ty_kind: TypeKind<I>,
 }
 
+impl<I: Interner> PartialEq for TypeData<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ty_kind == other.ty_kind
+    }
+}
+
+impl<I: Interner> Eq for TypeData<I> {}
+
+impl<I: Interner> Hash for TypeData<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ty_kind.hash(state)
+    }
+}
+
 /// TODO: write docs
 #[derive(Debug)]
 pub enum GenericArgData<I: Interner> {
     Type(Type<I>),
+    Const(ConstValue),
+}
+
+impl<I: Interner> PartialEq for GenericArgData<I> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Type(l0), Self::Type(r0)) => l0 == r0,
+            (Self::Const(l0), Self::Const(r0)) => l0 == r0,
+            _ => false,
+        }
+    }
+}
+
+impl<I: Interner> Eq for GenericArgData<I> {}
+
+impl<I: Interner> Hash for GenericArgData<I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Type(ty) => ty.hash(state),
+            Self::Const(value) => value.hash(state),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, test_interner::TestInterner};
+    use std::hash::{BuildHasher, RandomState};
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn supertraits_refer_only_to_generic_parameters() {
+        let interner = TestInterner::new();
+        let (_B, _PartialEq) = add_items!(*interner, {
+            struct B<T>;
+            trait PartialEq<Rhs>;
+        });
+        let bound = WhereClause::parse(interner, "impl<Self, T> Self as PartialEq<B<T>>").unwrap();
+        assert_eq!(
+            bound.check_trait_bound(interner, &[GenericArgType::Type]),
+            Ok(())
+        );
+        assert_eq!(
+            bound.check_trait_bound(interner, &[]),
+            Err(PatternError::UnknownPlaceholder { index: 2 })
+        );
+        assert_eq!(
+            bound.check_trait_bound(interner, &[GenericArgType::Const]),
+            Err(PatternError::KindMismatch {
+                index: 2,
+                expected: PatternKind::Const,
+                found: PatternKind::Type,
+            })
+        );
+        let error = interner
+            .new_trait("Eq".into(), [].into(), [bound].into())
+            .unwrap_err();
+        assert_eq!(error, PatternError::UnknownPlaceholder { index: 2 });
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn type_ids_hash_and_order_consistently_with_equality() {
+        let interner = TestInterner::new();
+        let (A, B, (Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        let type_ids = || {
+            [
+                TypeId::Adt(A),
+                TypeId::Adt(B),
+                TypeId::Scalar(Scalar::bool),
+                TypeId::Scalar(Scalar::UInt(UIntType::u8)),
+                TypeId::NonZero,
+                TypeId::Slice,
+                TypeId::Array,
+                TypeId::Tuple(0),
+                TypeId::Tuple(2),
+                TypeId::Ref(None),
+                TypeId::Ref(Some(RefQual::Mut)),
+                TypeId::Ptr(None),
+                TypeId::Ptr(Some(PtrQual::Mut)),
+                TypeId::Never,
+                TypeId::FnPtr(0),
+                TypeId::FnPtr(1),
+                TypeId::Dyn(Iterator),
+                TypeId::Projection(Item),
+            ]
+        };
+        let hasher = RandomState::new();
+        let (first, second) = (type_ids(), type_ids());
+        for (i, l) in first.iter().enumerate() {
+            for (j, r) in second.iter().enumerate() {
+                // Ids are listed in their order, which equality and ordering follow
+                assert_eq!(l == r, i == j, "{:?} and {:?}", l, r);
+                assert_eq!(l.cmp(r), i.cmp(&j), "{:?} and {:?}", l, r);
+                if l == r {
+                    assert_eq!(hasher.hash_one(l), hasher.hash_one(r), "{:?}", l);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,568 @@
+//! Trait goal solver.
+//!
+//! The solver answers questions of the form `Type: Trait<Args>` by matching the goal against
+//! every impl of the trait registered in the interner. Where-clauses of matching impls become
+//! nested goals, which are solved recursively.
+//!
+//! Projections `<Type as Trait<Args>>::Name` are normalized before goals are solved, by selecting
+//! the impl of `Type: Trait<Args>` and substituting the value of its associated type.
+//!
+//! Besides impls from the interner, goals can be proven by:
+//! - where-clauses of the `ParamEnv` the solver was created with, which are elaborated, so
+//!   `T0: Ord` also proves `T0: Eq`;
+//! - the built-in impl of trait objects: `dyn Trait<Args>: Trait<Args>` and its supertrait bounds
+//!   hold for every trait.
+//!
+//! Sources of candidates are tried in the following order, and the first source with a candidate
+//! that applies determines the solution:
+//! 1. Where-clauses of the `ParamEnv`. Generic code relies on its bounds and not on impls that
+//!    happen to apply, so an applicable where-clause shadows every impl. Several applicable
+//!    where-clauses make the goal ambiguous.
+//! 2. The built-in impl of trait objects.
+//! 3. Impls registered in the interner.
+//!
+//! If the implementor of a goal is an inference variable, every applicable candidate of every
+//! source is reported and the goal is ambiguous. The implementor may be a trait object, so the
+//! built-in impl of trait objects is always one of the candidates of such goals.
+
+use crate::{
+    elaborate::elaborate_patterns,
+    interner::{AssocTypeId, ImplId, Interner, TraitId},
+    param_env::ParamEnv,
+    patterns::{Pattern, PatternElement, PatternError, PatternSeq},
+    primitives::{TypeId, WhereClause},
+    unify::InferenceTable,
+};
+
+/// Goal of the form `Type: Trait<Args>`.
+///
+/// `pattern` has the same layout as a trait impl pattern: the implementor is followed by the
+/// generic arguments of the trait.
+#[derive(Clone, Copy, Debug)]
+pub struct TraitGoal<'a, I: Interner> {
+    pub trait_id: TraitId<I>,
+    pub pattern: &'a PatternSeq<I>,
+}
+
+impl<'a, I: Interner> TraitGoal<'a, I> {
+    /// Creates new `TraitGoal` by checking that `pattern` is a valid pattern for `trait_id`.
+    pub fn new(
+        interner: I,
+        trait_id: TraitId<I>,
+        pattern: &'a [PatternElement<I>],
+    ) -> Result<Self, PatternError> {
+        Ok(Self {
+            trait_id,
+            pattern: PatternSeq::new_trait_impl(interner, pattern, trait_id)?,
+        })
+    }
+
+    /// Writes goal in the form `Type: Trait<Args>`.
+    pub fn format(&self, interner: I, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        let (implementor, trait_args) = self.pattern.split_first();
+        implementor.format(interner, f)?;
+        let trait_data = interner.get_trait_by_id(self.trait_id);
+        write!(f, ": {}", interner.trait_data(&trait_data).name)?;
+        if let Some(trait_args) = trait_args {
+            write!(f, "<")?;
+            trait_args.format(interner, f)?;
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I: Interner> From<&'a WhereClause<I>> for TraitGoal<'a, I> {
+    fn from(clause: &'a WhereClause<I>) -> Self {
+        Self {
+            trait_id: clause.trait_id,
+            pattern: &clause.pattern,
+        }
+    }
+}
+
+impl<I: Interner> WhereClause<I> {
+    /// Writes bound in the form `Type: Trait<Args>`.
+    pub fn format(&self, interner: I, f: &mut dyn std::fmt::Write) -> std::fmt::Result {
+        TraitGoal::from(self).format(interner, f)
+    }
+}
+
+/// Source of an impl that proves a `TraitGoal`.
+#[derive(Clone, Copy, Debug)]
+pub enum Candidate<I: Interner> {
+    /// Impl registered in the interner.
+    Impl(ImplId<I>),
+    /// Built-in impl of `dyn Trait<Args>: Trait<Args>` or of one of its supertraits.
+    DynObject,
+    /// Where-clause at the given index of `ParamEnv::clauses`.
+    Assumption(usize),
+}
+
+/// Result of solving a `TraitGoal`.
+#[derive(Debug)]
+pub enum Solution<I: Interner> {
+    /// Exactly one impl applies to the goal.
+    Proven(Candidate<I>),
+    /// No impl applies to the goal.
+    Disproven,
+    /// Several impls may apply to the goal, so none of them can be selected.
+    Ambiguous(Vec<Candidate<I>>),
+}
+
+impl<I: Interner> Solution<I> {
+    /// Returns whether the goal is known to hold.
+    pub fn is_proven(&self) -> bool {
+        matches!(self, Solution::Proven(_))
+    }
+}
+
+/// Maximum depth of nested goals, deeper goals are considered ambiguous.
+const MAX_DEPTH: usize = 32;
+
+/// Whether an impl applies to a goal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Certainty {
+    Yes,
+    No,
+    Maybe,
+}
+
+/// Solver for `TraitGoal`s over impls registered in the interner.
+#[derive(Debug)]
+pub struct Solver<I: Interner> {
+    interner: I,
+    param_env: ParamEnv<I>,
+}
+
+impl<I: Interner> Solver<I> {
+    /// Creates solver for non-generic code, which has no assumptions.
+    pub fn new(interner: I) -> Self {
+        Self::with_param_env(interner, ParamEnv::empty())
+    }
+
+    /// Creates solver for generic code, which may use where-clauses of `param_env`.
+    pub fn with_param_env(interner: I, param_env: ParamEnv<I>) -> Self {
+        Self {
+            interner,
+            param_env,
+        }
+    }
+
+    pub fn interner(&self) -> I {
+        self.interner
+    }
+
+    pub fn param_env(&self) -> &ParamEnv<I> {
+        &self.param_env
+    }
+
+    /// Tries to prove `goal` by selecting one candidate, see module docs for their precedence.
+    ///
+    /// Impl is selected only if its header matches the goal and all of its where-clauses can be
+    /// proven. Impls with where-clauses that can be disproven are not considered at all.
+    pub fn solve(&self, goal: TraitGoal<'_, I>) -> Solution<I> {
+        self.solve_at_depth(goal, 0)
+    }
+
+    fn solve_at_depth(&self, goal: TraitGoal<'_, I>, depth: usize) -> Solution<I> {
+        let interner = self.interner;
+        let pattern = self.normalize_at_depth(goal.pattern, depth);
+        let goal = TraitGoal {
+            trait_id: goal.trait_id,
+            pattern: &pattern,
+        };
+        let impls = interner.candidate_impls(goal.trait_id, goal.pattern);
+        let assumptions: Vec<_> = self
+            .param_env
+            .matching_clauses(goal)
+            .into_iter()
+            .map(Candidate::Assumption)
+            .collect();
+        // Nested goals can't narrow down the implementor if it's completely unknown
+        let implementor_unknown = goal
+            .pattern
+            .first()
+            .is_some_and(|elem| elem.is_inference_var());
+        if depth > MAX_DEPTH || implementor_unknown {
+            let mut candidates = impls;
+            candidates.retain(|&impl_id| {
+                let impl_data = interner.get_impl_by_id(impl_id);
+                goal.pattern
+                    .matches(&interner.impl_data(&impl_data).pattern)
+            });
+            let dyn_object = implementor_unknown || self.is_dyn_object_of(goal);
+            let candidates: Vec<_> = assumptions
+                .into_iter()
+                .chain(dyn_object.then_some(Candidate::DynObject))
+                .chain(candidates.into_iter().map(Candidate::Impl))
+                .collect();
+            return match candidates.len() {
+                0 => Solution::Disproven,
+                _ => Solution::Ambiguous(candidates),
+            };
+        }
+        match assumptions.len() {
+            0 => {}
+            1 => return Solution::Proven(assumptions[0]),
+            _ => return Solution::Ambiguous(assumptions),
+        }
+        if self.is_dyn_object_of(goal) {
+            return Solution::Proven(Candidate::DynObject);
+        }
+        let mut certain = true;
+        let mut candidates = Vec::new();
+        for impl_id in impls {
+            match self.evaluate_impl(goal, impl_id, depth) {
+                Certainty::Yes => candidates.push(Candidate::Impl(impl_id)),
+                Certainty::Maybe => {
+                    certain = false;
+                    candidates.push(Candidate::Impl(impl_id));
+                }
+                Certainty::No => {}
+            }
+        }
+        match candidates.len() {
+            0 => Solution::Disproven,
+            1 if certain => Solution::Proven(candidates[0]),
+            _ => Solution::Ambiguous(candidates),
+        }
+    }
+
+    /// Checks whether `goal` has the form `dyn Trait<Args>: Trait<Args>`, or is implied by it
+    /// through supertraits of `Trait`.
+    fn is_dyn_object_of(&self, goal: TraitGoal<'_, I>) -> bool {
+        let (implementor, _) = goal.pattern.split_first();
+        let &PatternElement::TypeConstructor {
+            type_id: TypeId::Dyn(trait_id),
+            ..
+        } = implementor.first()
+        else {
+            return false;
+        };
+        // Object bound `dyn Trait<Args>: Trait<Args>` has trait arguments of the object type
+        let mut object_bound = implementor.to_vec();
+        if let Some(object_args) = implementor.args() {
+            object_bound.extend(object_args.iter());
+        }
+        // Safe because `implementor` and arguments of a type are valid patterns
+        let object_bound = unsafe { PatternSeq::new_boxed_unchecked(object_bound.into()) };
+        elaborate_patterns(self.interner, vec![(trait_id, object_bound)])
+            .iter()
+            .any(|(bound_id, bound)| {
+                *bound_id == goal.trait_id
+                    && InferenceTable::new().unify(goal.pattern, bound).is_ok()
+            })
+    }
+
+    /// Checks whether `impl_id` applies to `goal` by proving its where-clauses.
+    fn evaluate_impl(&self, goal: TraitGoal<'_, I>, impl_id: ImplId<I>, depth: usize) -> Certainty {
+        let interner = self.interner;
+        let impl_data = interner.get_impl_by_id(impl_id);
+        let impl_data = interner.impl_data(&impl_data);
+        let mut table = InferenceTable::new();
+        table.add_vars_of(goal.pattern);
+        let mut vars = Vec::new();
+        let header = table.instantiate_with(&impl_data.pattern, &mut vars);
+        if table.unify(goal.pattern, &header).is_err() {
+            return Certainty::No;
+        }
+        let mut certainty = Certainty::Yes;
+        for clause in &impl_data.where_clauses {
+            let nested = table.instantiate_with(&clause.pattern, &mut vars);
+            let nested = table.resolve(&nested);
+            let nested_goal = TraitGoal {
+                trait_id: clause.trait_id,
+                pattern: &nested,
+            };
+            match self.solve_at_depth(nested_goal, depth + 1) {
+                Solution::Proven(_) => {}
+                Solution::Disproven => return Certainty::No,
+                Solution::Ambiguous(_) => certainty = Certainty::Maybe,
+            }
+        }
+        certainty
+    }
+
+    /// Replaces projections in `pattern` with values of associated types of selected impls.
+    ///
+    /// Projections that can't be resolved (e.g. because their self type is a placeholder or impl
+    /// selection is ambiguous) are kept as they are.
+    pub fn normalize(&self, pattern: &PatternSeq<I>) -> Box<PatternSeq<I>> {
+        self.normalize_at_depth(pattern, 0)
+    }
+
+    fn normalize_at_depth(&self, pattern: &PatternSeq<I>, depth: usize) -> Box<PatternSeq<I>> {
+        let mut elems = Vec::with_capacity(pattern.len());
+        for pat in pattern {
+            self.normalize_pattern(pat, depth, &mut elems);
+        }
+        // Safe because every pattern of `pattern` was replaced by a valid pattern of the same kind
+        unsafe { PatternSeq::new_boxed_unchecked(elems.into()) }
+    }
+
+    fn normalize_pattern(
+        &self,
+        pattern: &Pattern<I>,
+        depth: usize,
+        out: &mut Vec<PatternElement<I>>,
+    ) {
+        let (Some(args), &PatternElement::TypeConstructor { type_id, .. }) =
+            (pattern.args(), pattern.first())
+        else {
+            out.extend(pattern.iter());
+            return;
+        };
+        let args = self.normalize_at_depth(args, depth);
+        if let TypeId::Projection(assoc_type_id) = type_id
+            && let Some(value) = self.project(assoc_type_id, &args, depth)
+        {
+            out.extend(value.iter());
+            return;
+        }
+        out.push(PatternElement::TypeConstructor {
+            args_length: args.len(),
+            type_id,
+        });
+        out.extend(args.iter());
+    }
+
+    /// Returns normalized value of `<Self as Trait<Args>>::Name`, where `args` are the self type
+    /// followed by generic arguments of the trait.
+    fn project(
+        &self,
+        assoc_type_id: AssocTypeId<I>,
+        args: &PatternSeq<I>,
+        depth: usize,
+    ) -> Option<Box<PatternSeq<I>>> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let interner = self.interner;
+        let assoc_type_data = interner.get_assoc_type_by_id(assoc_type_id);
+        let goal = TraitGoal {
+            trait_id: interner.assoc_type_data(&assoc_type_data).trait_id,
+            pattern: args,
+        };
+        let Solution::Proven(Candidate::Impl(impl_id)) = self.solve_at_depth(goal, depth + 1)
+        else {
+            return None;
+        };
+        let impl_data = interner.get_impl_by_id(impl_id);
+        let impl_data = interner.impl_data(&impl_data);
+        let value = impl_data.assoc_type_value(assoc_type_id)?;
+        let mut table = InferenceTable::new();
+        table.add_vars_of(args);
+        let mut vars = Vec::new();
+        let header = table.instantiate_with(&impl_data.pattern, &mut vars);
+        table
+            .unify(args, &header)
+            .expect("header of selected impl should unify with the goal");
+        let value = table.instantiate_with(value, &mut vars);
+        Some(self.normalize_at_depth(&table.resolve(&value), depth + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{add_items, primitives::WhereClause, test_interner::TestInterner};
+    use solver_macros::add_impls;
+
+    type Interner = &'static TestInterner;
+
+    /// Creates interner with `Clone` implemented for `A` and for `B<T>` where `T: Clone`, and
+    /// with traits `Copy: Clone` and `Debug` without impls.
+    #[allow(non_snake_case)]
+    fn clone_impls() -> Interner {
+        let interner = TestInterner::new();
+        let (A, B, _C, Clone, _Copy, _Debug) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct C;
+            trait Clone;
+            trait Copy: Clone;
+            trait Debug;
+        });
+        add_impls!(interner, {
+            impl A as Clone;
+            impl<T> B<T> as Clone where T: Clone;
+        });
+        interner
+    }
+
+    /// Describes a candidate by the impl header it refers to.
+    fn describe_candidate(interner: Interner, candidate: Candidate<Interner>) -> String {
+        match candidate {
+            Candidate::Impl(impl_id) => {
+                let impl_data = interner.get_impl(impl_id);
+                let mut repr = String::new();
+                impl_data
+                    .pattern
+                    .format_as_trait_impl(interner, impl_data.trait_id.unwrap(), &mut repr)
+                    .unwrap();
+                repr
+            }
+            Candidate::DynObject => "dyn".into(),
+            Candidate::Assumption(idx) => format!("assumption {}", idx),
+        }
+    }
+
+    /// Solves goal parsed from an impl header and describes its solution.
+    fn solve(solver: &Solver<Interner>, goal: &str) -> String {
+        let interner = solver.interner();
+        let (trait_id, pattern) = PatternSeq::parse_trait_impl(interner, goal).unwrap();
+        let goal = TraitGoal::new(interner, trait_id, &pattern).unwrap();
+        match solver.solve(goal) {
+            Solution::Proven(candidate) => describe_candidate(interner, candidate),
+            Solution::Disproven => "disproven".into(),
+            Solution::Ambiguous(candidates) => {
+                let candidates: Vec<_> = candidates
+                    .into_iter()
+                    .map(|candidate| describe_candidate(interner, candidate))
+                    .collect();
+                format!("ambiguous: {}", candidates.join(", "))
+            }
+        }
+    }
+
+    #[test]
+    fn solutions_of_goals() {
+        let solver = Solver::new(clone_impls());
+        for (goal, expected) in [
+            ("impl A as Clone", "impl A as Clone"),
+            ("impl C as Clone", "disproven"),
+            ("impl B<A> as Clone", "impl<T0> B<T0> as Clone"),
+            ("impl B<B<A>> as Clone", "impl<T0> B<T0> as Clone"),
+            ("impl B<C> as Clone", "disproven"),
+            ("impl B<B<C>> as Clone", "disproven"),
+            ("impl B<?> as Clone", "ambiguous: impl<T0> B<T0> as Clone"),
+            ("impl<T> T as Clone", "disproven"),
+            ("impl<T> B<T> as Clone", "disproven"),
+        ] {
+            assert_eq!(solve(&solver, goal), expected, "goal `{}`", goal);
+        }
+    }
+
+    #[test]
+    fn solutions_of_trait_object_goals() {
+        let solver = Solver::new(clone_impls());
+        for (goal, expected) in [
+            ("impl dyn Clone as Clone", "dyn"),
+            ("impl dyn Copy as Clone", "dyn"),
+            ("impl dyn Clone as Copy", "disproven"),
+            ("impl B<dyn Copy> as Clone", "impl<T0> B<T0> as Clone"),
+            // The implementor may be a trait object, which implements every trait
+            (
+                "impl ? as Clone",
+                "ambiguous: dyn, impl A as Clone, impl<T0> B<T0> as Clone",
+            ),
+            ("impl ? as Debug", "ambiguous: dyn"),
+        ] {
+            assert_eq!(solve(&solver, goal), expected, "goal `{}`", goal);
+        }
+    }
+
+    #[test]
+    fn solutions_of_goals_with_assumptions() {
+        let interner = clone_impls();
+        let clause = WhereClause::parse(interner, "impl<T> T as Copy").unwrap();
+        let solver = Solver::with_param_env(interner, ParamEnv::new(interner, &[clause]));
+        for (goal, expected) in [
+            // `T0: Copy` is elaborated to `T0: Clone` as the second clause
+            ("impl<T> T as Clone", "assumption 1"),
+            ("impl<T> T as Copy", "assumption 0"),
+            ("impl<T> T as Debug", "disproven"),
+            ("impl<T> B<T> as Clone", "impl<T0> B<T0> as Clone"),
+            ("impl<T, U> U as Clone", "disproven"),
+            ("impl A as Clone", "impl A as Clone"),
+            ("impl ? as Copy", "ambiguous: assumption 0, dyn"),
+        ] {
+            assert_eq!(solve(&solver, goal), expected, "goal `{}`", goal);
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn projections_are_normalized_by_selected_impls() {
+        let interner = TestInterner::new();
+        let (A, _B, Iter, Clone, (Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct Iter<T>;
+            trait Clone;
+            trait Iterator {
+                type Item;
+            }
+        });
+        add_impls!(interner, {
+            impl A as Clone;
+            impl<T> Iter<T> as Iterator {
+                type Item = T;
+            }
+        });
+        let solver = Solver::new(interner);
+        for (pattern, expected) in [
+            ("<Iter<A> as Iterator>::Item", "A"),
+            ("B<<Iter<B<A>> as Iterator>::Item>", "B<B<A>>"),
+            ("<Iter<<Iter<A> as Iterator>::Item> as Iterator>::Item", "A"),
+            ("<T0 as Iterator>::Item", "<T0 as Iterator>::Item"),
+            ("<A as Iterator>::Item", "<A as Iterator>::Item"),
+        ] {
+            let pattern = PatternSeq::parse(interner, pattern).unwrap();
+            let mut repr = String::new();
+            solver
+                .normalize(&pattern)
+                .format(interner, &mut repr)
+                .unwrap();
+            assert_eq!(repr, expected);
+        }
+        assert_eq!(
+            solve(&solver, "impl <Iter<A> as Iterator>::Item as Clone"),
+            "impl A as Clone"
+        );
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn solutions_of_tuple_and_function_pointer_goals() {
+        let interner = TestInterner::new();
+        let (A, B, _C, Clone, _Copy) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            struct C;
+            trait Clone;
+            trait Copy: Clone;
+        });
+        add_impls!(interner, {
+            impl A as Clone;
+            impl<T> B<T> as Clone where T: Clone;
+            impl () as Clone;
+            impl<T, U> (T, U) as Clone where T: Clone, U: Clone;
+            impl<T, U> fn(T) -> U as Clone;
+        });
+        let clause = WhereClause::parse(interner, "impl<T> T as Copy").unwrap();
+        let solver = Solver::with_param_env(interner, ParamEnv::new(interner, &[clause]));
+        for (goal, expected) in [
+            ("impl (B<A>, ()) as Clone", "impl<T0, T1> (T0, T1) as Clone"),
+            ("impl (A, (C, A)) as Clone", "disproven"),
+            ("impl (A,) as Clone", "disproven"),
+            (
+                "impl fn(A) -> B<C> as Clone",
+                "impl<T0, T1> fn(T0) -> T1 as Clone",
+            ),
+            (
+                "impl fn(&drop [*mut u8]) -> B<?> as Clone",
+                "impl<T0, T1> fn(T0) -> T1 as Clone",
+            ),
+            ("impl fn() as Clone", "disproven"),
+            // `T0: Clone` is proven by the elaborated assumption
+            (
+                "impl<T> (T, B<T>) as Clone",
+                "impl<T0, T1> (T0, T1) as Clone",
+            ),
+        ] {
+            assert_eq!(solve(&solver, goal), expected, "goal `{}`", goal);
+        }
+    }
+}
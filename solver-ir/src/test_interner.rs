@@ -0,0 +1,272 @@
+//! Interner used by unit tests of this crate.
+//!
+//! Tests leak the interner with `TestInterner::new`, so items are leaked as well and can be
+//! borrowed for `'static`. Use `*interner` as the interner expression of `add_items!`, which takes
+//! the interner by reference.
+
+use super::{
+    interner::{AdtId, AssocTypeId, GenericArg, ImplId, Interner, ItemId, TraitId, Type},
+    patterns::{ExactPatternSeq, PatternError, PatternSeq},
+    primitives::{
+        AdtData, AssocTypeData, AssocTypeValue, ConstValue, GenericArgData, GenericArgType,
+        ImplData, PtrQual, Scalar, TraitData, TypeData, UIntType, WhereClause,
+    },
+};
+use std::{cell::RefCell, num::NonZero, rc::Rc};
+
+#[derive(Debug)]
+enum TestItem {
+    Adt(AdtData),
+    Trait(TraitData<&'static TestInterner>),
+    Impl(ImplData<&'static TestInterner>),
+    AssocType(AssocTypeData<&'static TestInterner>),
+}
+
+/// Single threaded interner, which doesn't deduplicate interned data.
+#[derive(Debug, Default)]
+pub struct TestInterner {
+    items: RefCell<Vec<&'static TestItem>>,
+}
+
+impl TestInterner {
+    pub fn new() -> &'static Self {
+        Box::leak(Box::default())
+    }
+
+    fn get_item(&self, id: ItemId) -> &'static TestItem {
+        self.items.borrow()[id.get() as usize - 1]
+    }
+
+    fn push_item(&self, item: TestItem) -> ItemId {
+        let mut items = self.items.borrow_mut();
+        items.push(Box::leak(Box::new(item)));
+        ItemId(NonZero::new(items.len() as u32).unwrap())
+    }
+
+    fn item_ids_by(&self, mut pred: impl FnMut(&TestItem) -> bool) -> Vec<ItemId> {
+        let items = self.items.borrow();
+        (1..=items.len() as u32)
+            .zip(items.iter())
+            .filter(|(_, item)| pred(item))
+            .map(|(id, _)| ItemId(NonZero::new(id).unwrap()))
+            .collect()
+    }
+
+    pub fn new_adt(
+        &'static self,
+        name: Box<str>,
+        generic_args: Box<[GenericArgType]>,
+    ) -> AdtId<&'static Self> {
+        AdtId::new(self.push_item(TestItem::Adt(AdtData { name, generic_args })))
+    }
+
+    pub fn new_trait(
+        &'static self,
+        name: Box<str>,
+        generic_args: Box<[GenericArgType]>,
+        supertraits: Box<[WhereClause<&'static Self>]>,
+    ) -> Result<TraitId<&'static Self>, PatternError> {
+        for supertrait in &supertraits {
+            supertrait.check_trait_bound(self, &generic_args)?;
+        }
+        let data = TraitData {
+            name,
+            generic_args,
+            supertraits,
+        };
+        Ok(TraitId::new(self.push_item(TestItem::Trait(data))))
+    }
+
+    pub fn new_assoc_type(
+        &'static self,
+        trait_id: TraitId<&'static Self>,
+        name: Box<str>,
+    ) -> AssocTypeId<&'static Self> {
+        let data = AssocTypeData { name, trait_id };
+        AssocTypeId::new(self.push_item(TestItem::AssocType(data)))
+    }
+
+    pub fn get_impl(&'static self, id: ImplId<&'static Self>) -> &'static ImplData<&'static Self> {
+        self.get_impl_by_id(id)
+    }
+
+    pub fn new_trait_impl(
+        &'static self,
+        trait_id: TraitId<&'static Self>,
+        pattern: Box<ExactPatternSeq<&'static Self>>,
+        where_clauses: Box<[WhereClause<&'static Self>]>,
+        assoc_types: Box<[AssocTypeValue<&'static Self>]>,
+    ) -> Result<ImplId<&'static Self>, PatternError> {
+        PatternSeq::new_trait_impl(self, &pattern, trait_id)?;
+        let data = ImplData {
+            trait_id: Some(trait_id),
+            pattern,
+            where_clauses,
+            assoc_types,
+        };
+        Ok(ImplId::new(self.push_item(TestItem::Impl(data))))
+    }
+
+    pub fn new_inherent_impl(
+        &'static self,
+        pattern: Box<ExactPatternSeq<&'static Self>>,
+        where_clauses: Box<[WhereClause<&'static Self>]>,
+    ) -> Result<ImplId<&'static Self>, PatternError> {
+        PatternSeq::new_inherent_impl(self, &pattern)?;
+        let data = ImplData {
+            trait_id: None,
+            pattern,
+            where_clauses,
+            assoc_types: [].into(),
+        };
+        Ok(ImplId::new(self.push_item(TestItem::Impl(data))))
+    }
+}
+
+/// Returns tuple of types of every `TypeKind`, which contain `ty` wherever they have a component:
+/// `(B<ty>, u8, NonZero<ty>, [ty], [ty; 3], (ty,), &ty, *mut ty, !, fn(ty) -> ty, dyn Tr<ty>,
+/// <ty as Iterator>::Item)`, where `B`, `Tr` and `Item` are `adt`, `trait_id` and
+/// `assoc_type_id`.
+pub fn type_of_every_kind(
+    interner: &'static TestInterner,
+    ty: Type<&'static TestInterner>,
+    adt: AdtId<&'static TestInterner>,
+    trait_id: TraitId<&'static TestInterner>,
+    assoc_type_id: AssocTypeId<&'static TestInterner>,
+) -> Type<&'static TestInterner> {
+    let arg = || GenericArg::ty(interner, ty.clone());
+    Type::tuple(
+        interner,
+        [
+            Type::adt(interner, adt, [arg()]),
+            Type::scalar(interner, Scalar::UInt(UIntType::u8)),
+            Type::non_zero(interner, ty.clone()),
+            Type::slice(interner, ty.clone()),
+            Type::array(interner, ty.clone(), ConstValue(3)),
+            Type::tuple(interner, [ty.clone()]),
+            Type::reference(interner, None, ty.clone()),
+            Type::ptr(interner, Some(PtrQual::Mut), ty.clone()),
+            Type::never(interner),
+            Type::fn_ptr(interner, [ty.clone()], ty.clone()),
+            Type::dyn_trait(interner, trait_id, [arg()]),
+            Type::projection(interner, assoc_type_id, ty.clone(), []),
+        ],
+    )
+}
+
+impl Interner for &'static TestInterner {
+    type InternedType = Rc<TypeData<Self>>;
+    type InternedGenericArg = Rc<GenericArgData<Self>>;
+    type InternedSubstitution = Rc<[GenericArg<Self>]>;
+    type InternedAdtData = &'static AdtData;
+    type InternedTraitData = &'static TraitData<Self>;
+    type InternedImplData = &'static ImplData<Self>;
+    type InternedAssocTypeData = &'static AssocTypeData<Self>;
+
+    fn intern_type(self, data: TypeData<Self>) -> Self::InternedType {
+        Rc::new(data)
+    }
+
+    fn intern_generic_arg(self, data: GenericArgData<Self>) -> Self::InternedGenericArg {
+        Rc::new(data)
+    }
+
+    fn intern_substitution(self, args: Box<[GenericArg<Self>]>) -> Self::InternedSubstitution {
+        args.into()
+    }
+
+    fn type_data(self, ty: &Self::InternedType) -> &TypeData<Self> {
+        ty
+    }
+
+    fn generic_arg_data(self, arg: &Self::InternedGenericArg) -> &GenericArgData<Self> {
+        arg
+    }
+
+    fn substitution_data(self, subst: &Self::InternedSubstitution) -> &[GenericArg<Self>] {
+        subst
+    }
+
+    fn adt_data(self, adt: &Self::InternedAdtData) -> &AdtData {
+        adt
+    }
+
+    fn trait_data(self, r#trait: &Self::InternedTraitData) -> &TraitData<Self> {
+        r#trait
+    }
+
+    fn impl_data(self, r#impl: &Self::InternedImplData) -> &ImplData<Self> {
+        r#impl
+    }
+
+    fn assoc_type_data(self, assoc_type: &Self::InternedAssocTypeData) -> &AssocTypeData<Self> {
+        assoc_type
+    }
+
+    fn get_adt_by_id(self, id: AdtId<Self>) -> Self::InternedAdtData {
+        match self.get_item(*id) {
+            TestItem::Adt(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_trait_by_id(self, id: TraitId<Self>) -> Self::InternedTraitData {
+        match self.get_item(*id) {
+            TestItem::Trait(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_impl_by_id(self, id: ImplId<Self>) -> Self::InternedImplData {
+        match self.get_item(*id) {
+            TestItem::Impl(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_assoc_type_by_id(self, id: AssocTypeId<Self>) -> Self::InternedAssocTypeData {
+        match self.get_item(*id) {
+            TestItem::AssocType(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    fn adts(self) -> Vec<AdtId<Self>> {
+        self.item_ids_by(|item| matches!(item, TestItem::Adt(_)))
+            .into_iter()
+            .map(AdtId::new)
+            .collect()
+    }
+
+    fn traits(self) -> Vec<TraitId<Self>> {
+        self.item_ids_by(|item| matches!(item, TestItem::Trait(_)))
+            .into_iter()
+            .map(TraitId::new)
+            .collect()
+    }
+
+    fn trait_assoc_types(self, trait_id: TraitId<Self>) -> Vec<AssocTypeId<Self>> {
+        self.item_ids_by(
+            |item| matches!(item, TestItem::AssocType(data) if data.trait_id == trait_id),
+        )
+        .into_iter()
+        .map(AssocTypeId::new)
+        .collect()
+    }
+
+    fn trait_impls(self, trait_id: TraitId<Self>) -> Vec<ImplId<Self>> {
+        self.item_ids_by(
+            |item| matches!(item, TestItem::Impl(data) if data.trait_id == Some(trait_id)),
+        )
+        .into_iter()
+        .map(ImplId::new)
+        .collect()
+    }
+
+    fn inherent_impls(self) -> Vec<ImplId<Self>> {
+        self.item_ids_by(|item| matches!(item, TestItem::Impl(data) if data.trait_id.is_none()))
+            .into_iter()
+            .map(ImplId::new)
+            .collect()
+    }
+}
@@ -0,0 +1,577 @@
+//! Unification of patterns containing inference variables.
+//!
+//! Inference variables are tracked in an `InferenceTable`, which is a union-find structure over
+//! `InferenceVar`s where the root of every equivalence class may be bound to a pattern.
+//! Placeholders are treated as opaque types and consts: they unify only with themselves. To treat
+//! placeholders of a pattern as arbitrary types and consts (e.g. in impl patterns) they have to be
+//! instantiated with fresh inference variables first.
+
+use crate::{
+    interner::Interner,
+    patterns::{
+        ExactPatternSeq, InferenceVar, Pattern, PatternElement, PatternSeq, PlaceholderIndex,
+    },
+};
+
+/// Reason why two patterns can't be unified.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnificationError {
+    /// Patterns have different structure (e.g. different type constructors).
+    Mismatch,
+    /// Unification would bind the variable to a pattern containing the variable itself.
+    OccursCheck(InferenceVar),
+}
+
+#[derive(Debug)]
+struct VarEntry<I: Interner> {
+    parent: InferenceVar,
+    rank: u32,
+    value: Option<Box<PatternSeq<I>>>,
+}
+
+#[derive(Debug)]
+enum UndoEntry {
+    NewVar,
+    SetParent(InferenceVar, InferenceVar),
+    SetRank(InferenceVar, u32),
+    SetValue(InferenceVar),
+}
+
+/// Union-find table of inference variables and their values.
+///
+/// Variables are identified by their indices, so the same `InferenceVar` in different patterns
+/// refers to the same variable. The table grows automatically to cover all variables it sees.
+#[derive(Debug)]
+pub struct InferenceTable<I: Interner> {
+    vars: Vec<VarEntry<I>>,
+    undo_log: Vec<UndoEntry>,
+}
+
+impl<I: Interner> Default for InferenceTable<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Interner> InferenceTable<I> {
+    pub fn new() -> Self {
+        Self {
+            vars: Vec::new(),
+            undo_log: Vec::new(),
+        }
+    }
+
+    /// Returns the number of variables known to the table.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Returns whether the table knows no variables.
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// Creates a fresh unbound variable that doesn't occur in any pattern seen by the table.
+    pub fn new_var(&mut self) -> InferenceVar {
+        let var = InferenceVar(self.vars.len() as u32);
+        self.vars.push(VarEntry {
+            parent: var,
+            rank: 0,
+            value: None,
+        });
+        self.undo_log.push(UndoEntry::NewVar);
+        var
+    }
+
+    fn ensure_var(&mut self, var: InferenceVar) {
+        while self.vars.len() <= var.index() {
+            self.new_var();
+        }
+    }
+
+    fn entry(&self, var: InferenceVar) -> Option<&VarEntry<I>> {
+        self.vars.get(var.index())
+    }
+
+    /// Returns the representative of the equivalence class of `var`.
+    pub fn find(&self, mut var: InferenceVar) -> InferenceVar {
+        while let Some(entry) = self.entry(var) {
+            if entry.parent == var {
+                break;
+            }
+            var = entry.parent;
+        }
+        var
+    }
+
+    /// Returns the pattern `var` is bound to without resolving variables inside of it.
+    pub fn probe(&self, var: InferenceVar) -> Option<&PatternSeq<I>> {
+        self.entry(self.find(var))?.value.as_deref()
+    }
+
+    /// Unifies `a` with `b`, binding inference variables of both patterns.
+    ///
+    /// On failure the table is left unchanged.
+    pub fn unify(&mut self, a: &PatternSeq<I>, b: &PatternSeq<I>) -> Result<(), UnificationError> {
+        self.undo_log.clear();
+        let result = self.unify_seq(a, b);
+        if result.is_err() {
+            self.rollback();
+        }
+        self.undo_log.clear();
+        result
+    }
+
+    fn rollback(&mut self) {
+        while let Some(entry) = self.undo_log.pop() {
+            match entry {
+                UndoEntry::NewVar => {
+                    self.vars.pop();
+                }
+                UndoEntry::SetParent(var, parent) => self.vars[var.index()].parent = parent,
+                UndoEntry::SetRank(var, rank) => self.vars[var.index()].rank = rank,
+                UndoEntry::SetValue(var) => self.vars[var.index()].value = None,
+            }
+        }
+    }
+
+    fn unify_seq(&mut self, a: &PatternSeq<I>, b: &PatternSeq<I>) -> Result<(), UnificationError> {
+        let mut a_iter = a.into_iter();
+        let mut b_iter = b.into_iter();
+        loop {
+            match (a_iter.next(), b_iter.next()) {
+                (Some(a), Some(b)) => self.unify_pattern(a, b)?,
+                (None, None) => break Ok(()),
+                (Some(_), None) | (None, Some(_)) => break Err(UnificationError::Mismatch),
+            }
+        }
+    }
+
+    fn unify_pattern(&mut self, a: &Pattern<I>, b: &Pattern<I>) -> Result<(), UnificationError> {
+        if a.kind() != b.kind() {
+            return Err(UnificationError::Mismatch);
+        }
+        match (a.first().inference_var(), b.first().inference_var()) {
+            (Some(a_var), Some(b_var)) => return self.unify_vars(a_var, b_var),
+            (Some(var), None) => return self.bind(var, b),
+            (None, Some(var)) => return self.bind(var, a),
+            (None, None) => {}
+        }
+        match (a.first(), b.first()) {
+            (
+                PatternElement::TypeConstructor { type_id: a_ty, .. },
+                PatternElement::TypeConstructor { type_id: b_ty, .. },
+            ) => {
+                if a_ty != b_ty {
+                    return Err(UnificationError::Mismatch);
+                }
+                match (a.args(), b.args()) {
+                    (Some(a_args), Some(b_args)) => self.unify_seq(a_args, b_args),
+                    (None, None) => Ok(()),
+                    (Some(_), None) | (None, Some(_)) => Err(UnificationError::Mismatch),
+                }
+            }
+            // Remaining patterns are leaves, which unify only with themselves
+            (a_elem, b_elem) => {
+                if a_elem == b_elem {
+                    Ok(())
+                } else {
+                    Err(UnificationError::Mismatch)
+                }
+            }
+        }
+    }
+
+    fn unify_vars(&mut self, a: InferenceVar, b: InferenceVar) -> Result<(), UnificationError> {
+        self.ensure_var(a);
+        self.ensure_var(b);
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return Ok(());
+        }
+        match (self.probe(a), self.probe(b)) {
+            (None, None) => {
+                let (a_rank, b_rank) = (self.vars[a.index()].rank, self.vars[b.index()].rank);
+                let (root, child) = if a_rank < b_rank { (b, a) } else { (a, b) };
+                self.undo_log.push(UndoEntry::SetParent(child, child));
+                self.vars[child.index()].parent = root;
+                if a_rank == b_rank {
+                    self.undo_log.push(UndoEntry::SetRank(root, a_rank));
+                    self.vars[root.index()].rank += 1;
+                }
+                Ok(())
+            }
+            (Some(value), None) => {
+                let value = value.boxed();
+                self.bind_root(b, &value)
+            }
+            (None, Some(value)) => {
+                let value = value.boxed();
+                self.bind_root(a, &value)
+            }
+            (Some(a_value), Some(b_value)) => {
+                let (a_value, b_value) = (a_value.boxed(), b_value.boxed());
+                self.unify_seq(&a_value, &b_value)
+            }
+        }
+    }
+
+    fn bind(&mut self, var: InferenceVar, pattern: &Pattern<I>) -> Result<(), UnificationError> {
+        self.ensure_var(var);
+        let root = self.find(var);
+        match self.probe(root) {
+            Some(value) => {
+                let value = value.boxed();
+                self.unify_seq(&value, pattern)
+            }
+            None => self.bind_root(root, pattern),
+        }
+    }
+
+    fn bind_root(
+        &mut self,
+        root: InferenceVar,
+        pattern: &PatternSeq<I>,
+    ) -> Result<(), UnificationError> {
+        if self.occurs(root, pattern) {
+            return Err(UnificationError::OccursCheck(root));
+        }
+        self.undo_log.push(UndoEntry::SetValue(root));
+        self.vars[root.index()].value = Some(pattern.boxed());
+        Ok(())
+    }
+
+    /// Checks whether unbound `root` occurs in `pattern` after resolving its variables.
+    fn occurs(&self, root: InferenceVar, pattern: &PatternSeq<I>) -> bool {
+        pattern.iter().any(|elem| {
+            elem.inference_var().is_some_and(|var| {
+                let var = self.find(var);
+                var == root
+                    || self
+                        .probe(var)
+                        .is_some_and(|value| self.occurs(root, value))
+            })
+        })
+    }
+
+    /// Substitutes all bound variables of `pattern` with their values.
+    ///
+    /// Unbound variables are replaced with representatives of their equivalence classes, so
+    /// resolving both sides of a successful unification yields the same most general unifier.
+    pub fn resolve(&self, pattern: &PatternSeq<I>) -> Box<PatternSeq<I>> {
+        self.resolve_with(pattern, &mut |elem| elem)
+    }
+
+    /// Same as `resolve`, but additionally maps every placeholder and every representative of an
+    /// unbound variable with `leaf`.
+    pub fn resolve_with(
+        &self,
+        pattern: &PatternSeq<I>,
+        leaf: &mut dyn FnMut(PatternElement<I>) -> PatternElement<I>,
+    ) -> Box<PatternSeq<I>> {
+        let mut resolved = Vec::with_capacity(pattern.len());
+        self.resolve_into(pattern, &mut resolved, leaf);
+        // Safe because `resolve_into` substitutes leaves only with valid patterns of the same kind
+        unsafe { PatternSeq::new_boxed_unchecked(resolved.into()) }
+    }
+
+    fn resolve_into(
+        &self,
+        pattern: &PatternSeq<I>,
+        resolved: &mut Vec<PatternElement<I>>,
+        leaf: &mut dyn FnMut(PatternElement<I>) -> PatternElement<I>,
+    ) {
+        for pat in pattern {
+            let elem = *pat.first();
+            if let Some(var) = elem.inference_var() {
+                let var = self.find(var);
+                match self.probe(var) {
+                    Some(value) => self.resolve_into(value, resolved, leaf),
+                    None => {
+                        let elem = PatternElement::new_inference_var(elem.kind(), var);
+                        Self::push_leaf(resolved, leaf, elem)
+                    }
+                }
+                continue;
+            }
+            match elem {
+                PatternElement::TypeConstructor { type_id, .. } => {
+                    let start = resolved.len();
+                    resolved.push(PatternElement::TypeConstructor {
+                        args_length: 0,
+                        type_id,
+                    });
+                    if let Some(args) = pat.args() {
+                        self.resolve_into(args, resolved, leaf);
+                    }
+                    resolved[start] = PatternElement::TypeConstructor {
+                        args_length: resolved.len() - start - 1,
+                        type_id,
+                    };
+                }
+                elem => Self::push_leaf(resolved, leaf, elem),
+            }
+        }
+    }
+
+    fn push_leaf(
+        resolved: &mut Vec<PatternElement<I>>,
+        leaf: &mut dyn FnMut(PatternElement<I>) -> PatternElement<I>,
+        elem: PatternElement<I>,
+    ) {
+        let replacement = leaf(elem);
+        let is_leaf = match replacement {
+            PatternElement::TypeConstructor { args_length, .. } => args_length == 0,
+            PatternElement::TypePlaceholder(_)
+            | PatternElement::InferredType(_)
+            | PatternElement::ConstValue(_)
+            | PatternElement::ConstPlaceholder(_)
+            | PatternElement::InferredConst(_) => true,
+        };
+        assert!(
+            is_leaf && replacement.kind() == elem.kind(),
+            "leaf can only be replaced with another leaf of the same kind\n\
+             leaf: {:?}\nreplacement: {:?}",
+            elem,
+            replacement
+        );
+        resolved.push(replacement);
+    }
+
+    /// Makes sure that the table knows every inference variable of `pattern`.
+    pub fn add_vars_of(&mut self, pattern: &PatternSeq<I>) {
+        for var in pattern.iter().filter_map(PatternElement::inference_var) {
+            self.ensure_var(var);
+        }
+    }
+
+    /// Replaces placeholders of `pattern` with fresh inference variables.
+    ///
+    /// Returns instantiated pattern and variables that were created for placeholders, where
+    /// variable of placeholder `idx` is stored at `idx.index()`.
+    pub fn instantiate(
+        &mut self,
+        pattern: &PatternSeq<I>,
+    ) -> (Box<PatternSeq<I>>, Vec<InferenceVar>) {
+        let mut vars = Vec::new();
+        let instantiated = self.instantiate_with(pattern, &mut vars);
+        (instantiated, vars)
+    }
+
+    /// Replaces placeholders of `pattern` with variables from `vars`, where variable of
+    /// placeholder `idx` is stored at `idx.index()`.
+    ///
+    /// `vars` is extended with fresh variables for placeholders that don't have one yet, so the
+    /// same `vars` can be used to instantiate several patterns sharing placeholders.
+    pub fn instantiate_with(
+        &mut self,
+        pattern: &PatternSeq<I>,
+        vars: &mut Vec<InferenceVar>,
+    ) -> Box<PatternSeq<I>> {
+        self.add_vars_of(pattern);
+        if let Some(last) = pattern.placeholders().last() {
+            while vars.len() <= last.index() {
+                vars.push(self.new_var());
+            }
+        }
+        self.resolve_with(pattern, &mut |elem| match elem.placeholder() {
+            Some(idx) => PatternElement::new_inference_var(elem.kind(), vars[idx.index()]),
+            None => elem,
+        })
+    }
+}
+
+/// Patterns bound to inference variables of a `PatternSeq` by matching it against an
+/// `ExactPatternSeq`.
+///
+/// Bound patterns are expressed in terms of placeholders of the matched `ExactPatternSeq` and
+/// inference variables of the matching `PatternSeq`. Placeholders of the matching `PatternSeq` are
+/// shifted by `placeholder_offset`, so that they don't clash with placeholders of the matched
+/// `ExactPatternSeq`.
+#[derive(Debug)]
+pub struct Bindings<I: Interner> {
+    bindings: Vec<Option<Box<PatternSeq<I>>>>,
+    placeholder_offset: u32,
+}
+
+impl<I: Interner> Bindings<I> {
+    /// Returns the pattern bound to `var`, if `var` occurs in the matching pattern.
+    pub fn get(&self, var: InferenceVar) -> Option<&PatternSeq<I>> {
+        self.bindings.get(var.index())?.as_deref()
+    }
+
+    /// Returns an iterator over all bound variables in the order of their indices.
+    pub fn iter(&self) -> impl Iterator<Item = (InferenceVar, &PatternSeq<I>)> {
+        self.bindings
+            .iter()
+            .enumerate()
+            .filter_map(|(index, pat)| Some((InferenceVar(index as u32), pat.as_deref()?)))
+    }
+
+    /// Returns the offset added to placeholders of the matching pattern in bound patterns, which
+    /// is the number of placeholders of the matched pattern.
+    pub fn placeholder_offset(&self) -> u32 {
+        self.placeholder_offset
+    }
+}
+
+impl<I: Interner> PatternSeq<I> {
+    /// Computes the most general common instance of `self` and `other`.
+    ///
+    /// Inference variables with the same index in both sequences are considered to be the same
+    /// variable.
+    pub fn unify(&self, other: &Self) -> Result<Box<Self>, UnificationError> {
+        let mut table = InferenceTable::new();
+        table.unify(self, other)?;
+        Ok(table.resolve(self))
+    }
+
+    /// Matches `self` against `pattern` and returns patterns bound to inference variables of
+    /// `self`, or `None` if `self` doesn't match `pattern`.
+    ///
+    /// Placeholders of `self` in bound patterns are shifted past placeholders of `pattern`, see
+    /// `Bindings::placeholder_offset`.
+    pub fn match_bindings(&self, pattern: &ExactPatternSeq<I>) -> Option<Bindings<I>> {
+        let mut table = InferenceTable::new();
+        table.add_vars_of(self);
+        let (instance, placeholder_vars) = table.instantiate(pattern);
+        table.unify(self, &instance).ok()?;
+        let placeholder_offset = placeholder_vars.len() as u32;
+        let mut to_placeholder = |elem: PatternElement<I>| match elem.inference_var() {
+            Some(var) => placeholder_vars
+                .iter()
+                .position(|&placeholder_var| table.find(placeholder_var) == var)
+                .map_or(elem, |idx| {
+                    PatternElement::new_placeholder(elem.kind(), PlaceholderIndex(idx as u32))
+                }),
+            None => match elem.placeholder() {
+                Some(idx) => PatternElement::new_placeholder(
+                    elem.kind(),
+                    PlaceholderIndex(placeholder_offset + idx.0),
+                ),
+                None => elem,
+            },
+        };
+        let mut bindings = Vec::new();
+        for elem in self.iter() {
+            if let Some(var) = elem.inference_var() {
+                if bindings.len() <= var.index() {
+                    bindings.resize_with(var.index() + 1, || None);
+                }
+                if bindings[var.index()].is_none() {
+                    let var_pattern = [*elem];
+                    // Safe because single inference variable is a valid `PatternSeq`
+                    let var_pattern = unsafe { PatternSeq::new_unchecked(&var_pattern) };
+                    bindings[var.index()] =
+                        Some(table.resolve_with(var_pattern, &mut to_placeholder));
+                }
+            }
+        }
+        Some(Bindings {
+            bindings,
+            placeholder_offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{primitives::GenericArgType, test_interner::TestInterner};
+
+    type Interner = &'static TestInterner;
+
+    /// Creates interner with a single ADT `B<T>`, and parser of patterns referring to it.
+    fn parser() -> impl Fn(&str) -> Box<PatternSeq<Interner>> {
+        let interner = TestInterner::new();
+        interner.new_adt("B".into(), [GenericArgType::Type].into());
+        move |text| PatternSeq::parse(interner, text).unwrap()
+    }
+
+    #[test]
+    fn unify_binds_variables_of_both_sides() {
+        let parse = parser();
+        // Variables are numbered in order of their first occurrence in each pattern
+        let unified = parse("?x, ?y").unify(&parse("u8, B<?x>")).unwrap();
+        assert_eq!(unified[..], parse("u8, B<u8>")[..]);
+        let unified = parse("?x, B<?y>").unify(&parse("u8, B<u8>")).unwrap();
+        assert_eq!(unified[..], parse("u8, B<u8>")[..]);
+    }
+
+    #[test]
+    fn unify_fails_on_mismatch() {
+        let parse = parser();
+        assert_eq!(
+            parse("B<u8>").unify(&parse("B<u16>")),
+            Err(UnificationError::Mismatch)
+        );
+        // The same index refers to the same variable in both patterns
+        assert_eq!(
+            parse("?x, u8").unify(&parse("B<u8>, ?x")),
+            Err(UnificationError::Mismatch)
+        );
+        assert_eq!(
+            parse("u8, T0").unify(&parse("u8, T1")),
+            Err(UnificationError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn unify_checks_occurrences() {
+        let parse = parser();
+        assert_eq!(
+            parse("?x").unify(&parse("B<?x>")),
+            Err(UnificationError::OccursCheck(InferenceVar(0)))
+        );
+        // `?x` occurs in its own value only after it is unified with `?y`
+        assert!(matches!(
+            parse("?x, ?y, ?y").unify(&parse("?x, ?x, B<?x>")),
+            Err(UnificationError::OccursCheck(_))
+        ));
+    }
+
+    #[test]
+    fn failed_unification_rolls_back() {
+        let parse = parser();
+        let mut table = InferenceTable::new();
+        let error = table.unify(&parse("?x, ?y, u8"), &parse("u8, ?x, B<u8>"));
+        assert_eq!(error, Err(UnificationError::Mismatch));
+        assert!(table.is_empty());
+        table.add_vars_of(&parse("?x, ?y"));
+        let error = table.unify(&parse("?x, ?y, u8"), &parse("u8, ?x, B<u8>"));
+        assert_eq!(error, Err(UnificationError::Mismatch));
+        assert_eq!(table.len(), 2);
+        assert!(table.probe(InferenceVar(0)).is_none());
+        assert_eq!(table.find(InferenceVar(1)), InferenceVar(1));
+        // Variables are still usable after rollback
+        table.unify(&parse("?x, ?y"), &parse("B<u8>, ?x")).unwrap();
+        let resolved = table.resolve(&parse("?x, ?y"));
+        assert_eq!(resolved[..], parse("B<u8>, B<u8>")[..]);
+    }
+
+    #[test]
+    fn instantiate_creates_fresh_variables_for_placeholders() {
+        let parse = parser();
+        let mut table = InferenceTable::new();
+        table.add_vars_of(&parse("?x"));
+        let (instance, vars) = table.instantiate(&parse("B<T1>, T0, T1"));
+        assert_eq!(vars, [InferenceVar(1), InferenceVar(2)]);
+        assert_eq!(instance[..], parse("?a, ?b, ?c, B<?c>, ?b, ?c")[3..]);
+    }
+
+    #[test]
+    fn match_bindings_distinguishes_placeholders_of_both_sides() {
+        let parse = parser();
+        let exact = |text| ExactPatternSeq::new(&parse(text)).unwrap().boxed();
+        // `T0` of the matched pattern is bound to `T0` of `self`, which is shifted to `T1`
+        let bindings = parse("?x, T0").match_bindings(&exact("T0, T0")).unwrap();
+        assert_eq!(bindings.placeholder_offset(), 1);
+        assert_eq!(bindings.get(InferenceVar(0)).unwrap()[..], parse("T1")[..]);
+        // `?x` is bound to `T0` of the matched pattern itself
+        let bindings = parse("?x, T0").match_bindings(&exact("T0, T1")).unwrap();
+        assert_eq!(bindings.placeholder_offset(), 2);
+        assert_eq!(bindings.get(InferenceVar(0)).unwrap()[..], parse("T0")[..]);
+        // Placeholders of `self` are opaque
+        assert!(parse("u8, T0").match_bindings(&exact("T0, u8")).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! Traversal of interned types.
+//!
+//! `TypeVisitor` is called for every type and const reachable from a value. Overriding
+//! `visit_type` intercepts a type; calling `Type::super_visit_with` from it continues into the
+//! components of the type. Visitors stop early by returning `ControlFlow::Break`.
+
+use crate::{
+    interner::{GenericArg, Interner, Substitution, Type},
+    primitives::{ConstValue, GenericArgData, TypeKind},
+};
+use std::ops::ControlFlow;
+
+pub trait TypeVisitor<I: Interner> {
+    /// Value returned when the visitor stops early.
+    type BreakTy;
+
+    fn interner(&self) -> I;
+
+    /// Visits `ty`, by default by visiting its components.
+    fn visit_type(&mut self, ty: &Type<I>) -> ControlFlow<Self::BreakTy> {
+        ty.super_visit_with(self)
+    }
+
+    /// Visits const argument, e.g. the length of an array.
+    fn visit_const(&mut self, value: ConstValue) -> ControlFlow<Self::BreakTy> {
+        let _ = value;
+        ControlFlow::Continue(())
+    }
+}
+
+/// Values that contain types which can be visited by a `TypeVisitor`.
+pub trait TypeVisitable<I: Interner> {
+    fn visit_with<V: TypeVisitor<I> + ?Sized>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy>;
+}
+
+impl<I: Interner> Type<I> {
+    /// Visits components of `self`, without visiting `self` itself.
+    pub fn super_visit_with<V: TypeVisitor<I> + ?Sized>(
+        &self,
+        visitor: &mut V,
+    ) -> ControlFlow<V::BreakTy> {
+        let interner = visitor.interner();
+        match self.kind(interner) {
+            TypeKind::Adt(_, subst)
+            | TypeKind::Tuple(subst)
+            | TypeKind::FnPtr(subst)
+            | TypeKind::Dyn(_, subst)
+            | TypeKind::Projection(_, subst) => subst.visit_with(visitor),
+            TypeKind::NonZero(ty)
+            | TypeKind::Slice(ty)
+            | TypeKind::Ref(_, ty)
+            | TypeKind::Ptr(_, ty) => ty.visit_with(visitor),
+            &TypeKind::Array(ref ty, len) => {
+                ty.visit_with(visitor)?;
+                visitor.visit_const(len)
+            }
+            TypeKind::Scalar(_) | TypeKind::Never => ControlFlow::Continue(()),
+        }
+    }
+}
+
+impl<I: Interner> TypeVisitable<I> for Type<I> {
+    fn visit_with<V: TypeVisitor<I> + ?Sized>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        visitor.visit_type(self)
+    }
+}
+
+impl<I: Interner> TypeVisitable<I> for GenericArg<I> {
+    fn visit_with<V: TypeVisitor<I> + ?Sized>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        match self.data(visitor.interner()) {
+            GenericArgData::Type(ty) => ty.visit_with(visitor),
+            &GenericArgData::Const(value) => visitor.visit_const(value),
+        }
+    }
+}
+
+impl<I: Interner> TypeVisitable<I> for Substitution<I> {
+    fn visit_with<V: TypeVisitor<I> + ?Sized>(&self, visitor: &mut V) -> ControlFlow<V::BreakTy> {
+        for arg in self.data(visitor.interner()) {
+            arg.visit_with(visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        add_items,
+        interner::AdtId,
+        test_interner::{TestInterner, type_of_every_kind},
+    };
+
+    type Interner = &'static TestInterner;
+
+    /// Counts visited types and occurrences of an ADT, and collects visited consts.
+    struct CountAdt {
+        interner: Interner,
+        adt_id: AdtId<Interner>,
+        types: usize,
+        occurrences: usize,
+        consts: Vec<ConstValue>,
+    }
+
+    impl TypeVisitor<Interner> for CountAdt {
+        type BreakTy = ();
+
+        fn interner(&self) -> Interner {
+            self.interner
+        }
+
+        fn visit_type(&mut self, ty: &Type<Interner>) -> ControlFlow<()> {
+            self.types += 1;
+            if matches!(ty.kind(self.interner), TypeKind::Adt(adt_id, _) if *adt_id == self.adt_id)
+            {
+                self.occurrences += 1;
+            }
+            ty.super_visit_with(self)
+        }
+
+        fn visit_const(&mut self, value: ConstValue) -> ControlFlow<()> {
+            self.consts.push(value);
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn visitors_reach_components_of_every_kind_of_type() {
+        let interner = TestInterner::new();
+        let (A, B, PartialEq, (_Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        let a = Type::adt(interner, A, []);
+        let ty = type_of_every_kind(interner, a, B, PartialEq, Item);
+        let mut visitor = CountAdt {
+            interner,
+            adt_id: A,
+            types: 0,
+            occurrences: 0,
+            consts: Vec::new(),
+        };
+        assert_eq!(ty.visit_with(&mut visitor), ControlFlow::Continue(()));
+        // The tuple, its 12 components and `A` in all of them but `u8` and `!`, where
+        // `fn(A) -> A` contains `A` twice
+        assert_eq!(visitor.types, 1 + 12 + 11);
+        assert_eq!(visitor.occurrences, 11);
+        assert_eq!(visitor.consts, [ConstValue(3)]);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn visitors_visit_const_arguments() {
+        let interner = TestInterner::new();
+        let (A, Arr) = add_items!(*interner, {
+            struct A;
+            struct Arr<T, const N: usize>;
+        });
+        let a = Type::adt(interner, A, []);
+        let args = [
+            GenericArg::ty(interner, a.clone()),
+            GenericArg::constant(interner, ConstValue(2)),
+        ];
+        let ty = Type::array(interner, Type::adt(interner, Arr, args), ConstValue(3));
+        let mut visitor = CountAdt {
+            interner,
+            adt_id: A,
+            types: 0,
+            occurrences: 0,
+            consts: Vec::new(),
+        };
+        assert_eq!(ty.visit_with(&mut visitor), ControlFlow::Continue(()));
+        assert_eq!(visitor.occurrences, 1);
+        assert_eq!(visitor.consts, [ConstValue(2), ConstValue(3)]);
+    }
+
+    /// Stops at the never type with the number of types visited before.
+    struct BreakAtNever {
+        interner: Interner,
+        visited: usize,
+    }
+
+    impl TypeVisitor<Interner> for BreakAtNever {
+        type BreakTy = usize;
+
+        fn interner(&self) -> Interner {
+            self.interner
+        }
+
+        fn visit_type(&mut self, ty: &Type<Interner>) -> ControlFlow<usize> {
+            self.visited += 1;
+            match ty.kind(self.interner) {
+                TypeKind::Never => ControlFlow::Break(self.visited),
+                _ => ty.super_visit_with(self),
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn visitors_stop_at_the_first_break() {
+        let interner = TestInterner::new();
+        let (A, B, PartialEq, (_Iterator, Item)) = add_items!(*interner, {
+            struct A;
+            struct B<T>;
+            trait PartialEq<Rhs>;
+            trait Iterator {
+                type Item;
+            }
+        });
+        let a = Type::adt(interner, A, []);
+        let ty = type_of_every_kind(interner, a, B, PartialEq, Item);
+        let mut visitor = BreakAtNever {
+            interner,
+            visited: 0,
+        };
+        // The tuple and its components up to `!` in prefix order, and nothing after `!`
+        assert_eq!(ty.visit_with(&mut visitor), ControlFlow::Break(17));
+        assert_eq!(visitor.visited, 17);
+    }
+}
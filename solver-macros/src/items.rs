@@ -1,22 +1,110 @@
-use syn::{Ident, Token, braced, parse::Parse, token::Brace};
+use syn::{Ident, Token, braced, parse::Parse, punctuated::Punctuated, token::Brace};
 
 use crate::{
     patterns::ToPatternTokens as _,
-    types::{GenericArgs, Path, Type},
+    types::{Path, Type},
 };
 
+#[allow(dead_code)]
 enum ImplBody {
     Marker(Token![;]),
-    Common(Brace),
+    Common(Brace, Vec<AssocTypeItem>),
 }
 
+/// Value of an associated type `type Name = Type;` in an impl body.
+#[allow(dead_code)]
+pub struct AssocTypeItem {
+    type_token: Token![type],
+    name: Ident,
+    eq_token: Token![=],
+    value: Type,
+    semi_token: Token![;],
+}
+
+impl AssocTypeItem {
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn value_ty(&self) -> &Type {
+        &self.value
+    }
+}
+
+/// Generic parameter `T` or `const N: Type` of an impl.
+#[allow(dead_code)]
+pub struct GenericParam {
+    const_token: Option<Token![const]>,
+    ident: Ident,
+    const_ty: Option<(Token![:], Ident)>,
+}
+
+impl GenericParam {
+    pub fn ident(&self) -> &Ident {
+        &self.ident
+    }
+
+    pub fn is_const(&self) -> bool {
+        self.const_token.is_some()
+    }
+}
+
+#[allow(dead_code)]
+pub struct ImplGenerics {
+    lt_token: Token![<],
+    params: Punctuated<GenericParam, Token![,]>,
+    gt_token: Token![>],
+}
+
+impl ImplGenerics {
+    pub fn iter(&self) -> impl Iterator<Item = &GenericParam> {
+        self.params.iter()
+    }
+}
+
+#[allow(dead_code)]
+pub struct WherePredicate {
+    bounded: Type,
+    colon_token: Token![:],
+    bounds: Punctuated<Path, Token![+]>,
+}
+
+impl WherePredicate {
+    pub fn bounded_ty(&self) -> &Type {
+        &self.bounded
+    }
+
+    pub fn bounds(&self) -> impl Iterator<Item = &Path> {
+        self.bounds.iter()
+    }
+}
+
+#[allow(dead_code)]
+pub struct WhereClauses {
+    where_token: Token![where],
+    predicates: Punctuated<WherePredicate, Token![,]>,
+}
+
+impl WhereClauses {
+    pub fn iter(&self) -> impl Iterator<Item = &WherePredicate> {
+        self.predicates.iter()
+    }
+}
+
+#[allow(dead_code)]
 pub struct InherentImpl {
     impl_token: Token![impl],
+    generics: Option<ImplGenerics>,
     implementor: Type,
+    where_clauses: Option<WhereClauses>,
     body: ImplBody,
 }
 
 impl InherentImpl {
+    pub fn generics(&self) -> Option<&ImplGenerics> {
+        self.generics.as_ref()
+    }
+
     pub fn implementor_ty(&self) -> &Type {
         &self.implementor
     }
@@ -26,25 +114,32 @@ impl InherentImpl {
     }
 }
 
+#[allow(dead_code)]
 pub struct TraitImpl {
     impl_token: Token![impl],
+    generics: Option<ImplGenerics>,
     implementor: Type,
     as_token: Token![as],
     r#trait: Path,
+    where_clauses: Option<WhereClauses>,
     body: ImplBody,
 }
 
 impl TraitImpl {
+    pub fn generics(&self) -> Option<&ImplGenerics> {
+        self.generics.as_ref()
+    }
+
     pub fn implementor_ty(&self) -> &Type {
         &self.implementor
     }
 
-    pub fn trait_name(&self) -> &Ident {
-        &self.r#trait.ident()
+    pub fn trait_path(&self) -> &Path {
+        &self.r#trait
     }
 
-    pub fn trait_args(&self) -> Option<&GenericArgs> {
-        self.r#trait.args()
+    pub fn trait_name(&self) -> &Ident {
+        self.r#trait.ident()
     }
 
     pub fn has_inference_vars(&self) -> bool {
@@ -58,6 +153,25 @@ pub enum Impl {
 }
 
 impl Impl {
+    pub fn where_predicates(&self) -> impl Iterator<Item = &WherePredicate> {
+        let where_clauses = match self {
+            Impl::Inherent(inherent) => &inherent.where_clauses,
+            Impl::Trait(tr) => &tr.where_clauses,
+        };
+        where_clauses.iter().flat_map(WhereClauses::iter)
+    }
+
+    pub fn assoc_types(&self) -> &[AssocTypeItem] {
+        let body = match self {
+            Impl::Inherent(inherent) => &inherent.body,
+            Impl::Trait(tr) => &tr.body,
+        };
+        match body {
+            ImplBody::Marker(_) => &[],
+            ImplBody::Common(_, assoc_types) => assoc_types,
+        }
+    }
+
     pub fn has_inference_vars(&self) -> bool {
         match self {
             Impl::Inherent(inherent) => inherent.has_inference_vars(),
@@ -72,31 +186,150 @@ impl Parse for ImplBody {
         if lookahead.peek(Token![;]) {
             Ok(Self::Marker(input.parse()?))
         } else if lookahead.peek(Brace) {
-            let _content;
-            let braces = braced!(_content in input);
-            Ok(Self::Common(braces))
+            let content;
+            let braces = braced!(content in input);
+            let mut assoc_types = Vec::new();
+            while !content.is_empty() {
+                assoc_types.push(content.parse()?);
+            }
+            Ok(Self::Common(braces, assoc_types))
         } else {
             Err(lookahead.error())
         }
     }
 }
 
+impl Parse for AssocTypeItem {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            type_token: input.parse()?,
+            name: input.parse()?,
+            eq_token: input.parse()?,
+            value: input.parse()?,
+            semi_token: input.parse()?,
+        })
+    }
+}
+
+impl Parse for GenericParam {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![const]) {
+            Ok(Self {
+                const_token: Some(input.parse()?),
+                ident: input.parse()?,
+                const_ty: Some((input.parse()?, input.parse()?)),
+            })
+        } else {
+            Ok(Self {
+                const_token: None,
+                ident: input.parse()?,
+                const_ty: None,
+            })
+        }
+    }
+}
+
+impl Parse for ImplGenerics {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            lt_token: input.parse()?,
+            params: {
+                let mut params = Punctuated::new();
+                loop {
+                    if input.peek(Token![>]) {
+                        break;
+                    }
+                    params.push_value(input.parse()?);
+                    if input.peek(Token![>]) {
+                        break;
+                    }
+                    params.push_punct(input.parse()?);
+                }
+                params
+            },
+            gt_token: input.parse()?,
+        })
+    }
+}
+
+impl Parse for WherePredicate {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            bounded: input.parse()?,
+            colon_token: input.parse()?,
+            bounds: {
+                let mut bounds = Punctuated::new();
+                bounds.push_value(input.parse()?);
+                while input.peek(Token![+]) {
+                    bounds.push_punct(input.parse()?);
+                    bounds.push_value(input.parse()?);
+                }
+                bounds
+            },
+        })
+    }
+}
+
+impl Parse for WhereClauses {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            where_token: input.parse()?,
+            predicates: {
+                let mut predicates = Punctuated::new();
+                loop {
+                    if input.peek(Token![;]) || input.peek(Brace) {
+                        break;
+                    }
+                    predicates.push_value(input.parse()?);
+                    if input.peek(Token![;]) || input.peek(Brace) {
+                        break;
+                    }
+                    predicates.push_punct(input.parse()?);
+                }
+                predicates
+            },
+        })
+    }
+}
+
+fn parse_where_clauses(input: syn::parse::ParseStream) -> syn::Result<Option<WhereClauses>> {
+    if input.peek(Token![where]) {
+        Ok(Some(input.parse()?))
+    } else {
+        Ok(None)
+    }
+}
+
 impl Parse for Impl {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let impl_token = input.parse()?;
+        // `impl <T as Trait>::Name` starts with a projection rather than with generics
+        let generics = if input.peek(Token![<])
+            && (input.peek2(Token![>])
+                || input.peek2(Token![const])
+                || input.peek2(Ident) && (input.peek3(Token![,]) || input.peek3(Token![>])))
+        {
+            Some(input.parse()?)
+        } else {
+            None
+        };
         let implementor = input.parse()?;
         if input.peek(Token![as]) {
             Ok(Self::Trait(TraitImpl {
                 impl_token,
+                generics,
                 implementor,
                 as_token: input.parse()?,
                 r#trait: input.parse()?,
+                where_clauses: parse_where_clauses(input)?,
                 body: input.parse()?,
             }))
         } else {
             Ok(Self::Inherent(InherentImpl {
                 impl_token,
+                generics,
                 implementor,
+                where_clauses: parse_where_clauses(input)?,
                 body: input.parse()?,
             }))
         }
@@ -7,3 +7,8 @@ mod types;
 pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     patterns::impl_patterns(input)
 }
+
+#[proc_macro]
+pub fn add_impls(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    patterns::add_impls(input)
+}
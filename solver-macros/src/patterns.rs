@@ -1,16 +1,122 @@
-use crate::items::{Impl, InherentImpl, TraitImpl};
+use crate::{
+    items::{AssocTypeItem, Impl, ImplGenerics, InherentImpl, TraitImpl, WherePredicate},
+    types::{Path, Type},
+};
 use quote::quote;
+use std::collections::HashMap;
 use syn::{Expr, Ident, Token, braced, parse::Parse, parse_macro_input, token::Brace};
 
+/// Kind of a pattern element, which decides e.g. whether a placeholder is a type or a const.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ElementKind {
+    Type,
+    Const,
+}
+
+impl ElementKind {
+    /// Creates tokens of a placeholder pattern element of this kind.
+    pub fn placeholder_tokens(
+        self,
+        ir_crate: &Ident,
+        placeholder: u32,
+    ) -> proc_macro2::TokenStream {
+        let variant = match self {
+            ElementKind::Type => quote! { TypePlaceholder },
+            ElementKind::Const => quote! { ConstPlaceholder },
+        };
+        quote! {
+            #ir_crate::patterns::PatternElement::#variant(
+                #ir_crate::patterns::PlaceholderIndex(#placeholder)
+            ),
+        }
+    }
+
+    /// Creates tokens of an inference variable pattern element of this kind.
+    pub fn inference_var_tokens(self, ir_crate: &Ident, var: u32) -> proc_macro2::TokenStream {
+        let variant = match self {
+            ElementKind::Type => quote! { InferredType },
+            ElementKind::Const => quote! { InferredConst },
+        };
+        quote! {
+            #ir_crate::patterns::PatternElement::#variant(
+                #ir_crate::patterns::InferenceVar(#var)
+            ),
+        }
+    }
+}
+
+/// Names and variables that are shared between all patterns of a single impl.
+#[derive(Default)]
+pub struct PatternScope {
+    inference_vars: u32,
+    named_inference_vars: HashMap<String, u32>,
+    placeholders: u32,
+    generic_params: HashMap<String, (u32, ElementKind)>,
+}
+
+impl PatternScope {
+    /// Assigns placeholders to generic parameters of an impl in order of their declaration.
+    pub fn declare_generics(&mut self, generics: Option<&ImplGenerics>) {
+        for param in generics.into_iter().flat_map(ImplGenerics::iter) {
+            let placeholder = self.new_placeholder();
+            let kind = if param.is_const() {
+                ElementKind::Const
+            } else {
+                ElementKind::Type
+            };
+            self.generic_params
+                .insert(param.ident().to_string(), (placeholder, kind));
+        }
+    }
+
+    /// Returns placeholder and kind of the generic parameter named `name`, if it was declared.
+    pub fn generic_param(&self, name: &Ident) -> Option<(u32, ElementKind)> {
+        self.generic_params.get(&name.to_string()).copied()
+    }
+
+    /// Allocates index for a fresh placeholder.
+    pub fn new_placeholder(&mut self) -> u32 {
+        let placeholder = self.placeholders;
+        self.placeholders += 1;
+        placeholder
+    }
+
+    /// Allocates index for a fresh inference variable.
+    pub fn new_inference_var(&mut self) -> u32 {
+        let var = self.inference_vars;
+        self.inference_vars += 1;
+        var
+    }
+
+    /// Returns index of the inference variable named `name`, allocating it on first use.
+    pub fn named_inference_var(&mut self, name: &Ident) -> u32 {
+        if let Some(&var) = self.named_inference_vars.get(&name.to_string()) {
+            return var;
+        }
+        let var = self.new_inference_var();
+        self.named_inference_vars.insert(name.to_string(), var);
+        var
+    }
+}
+
 pub trait ToPatternTokens {
-    fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream);
+    fn to_pattern_tokens(
+        &self,
+        ir_crate: &Ident,
+        scope: &mut PatternScope,
+    ) -> (usize, proc_macro2::TokenStream);
 
     fn has_inference_vars(&self) -> bool;
 }
 
 impl InherentImpl {
-    fn to_pattern_tokens(&self, ir_crate: &Ident) -> proc_macro2::TokenStream {
-        let implementor = self.implementor_ty().to_pattern_tokens(ir_crate).1;
+    fn to_pattern_tokens(
+        &self,
+        ir_crate: &Ident,
+        scope: &mut PatternScope,
+    ) -> proc_macro2::TokenStream {
+        scope.declare_generics(self.generics());
+        let implementor = self.implementor_ty().to_pattern_tokens(ir_crate, scope).1;
         quote! {
             [ #implementor ]
         }
@@ -18,21 +124,91 @@ impl InherentImpl {
 }
 
 impl TraitImpl {
-    fn to_pattern_tokens(&self, ir_crate: &Ident) -> proc_macro2::TokenStream {
-        let implementor = self.implementor_ty().to_pattern_tokens(ir_crate).1;
-        if let Some(args) = self.trait_args() {
-            let args = args.iter().map(|arg| arg.to_pattern_tokens(ir_crate).1);
-            quote! {
-                [ #implementor #( #args )* ]
-            }
-        } else {
-            quote! {
-                [ #implementor ]
+    fn to_pattern_tokens(
+        &self,
+        ir_crate: &Ident,
+        scope: &mut PatternScope,
+    ) -> proc_macro2::TokenStream {
+        scope.declare_generics(self.generics());
+        bound_pattern_tokens(self.implementor_ty(), self.trait_path(), ir_crate, scope)
+    }
+}
+
+impl WherePredicate {
+    fn to_where_clause_tokens(
+        &self,
+        ir_crate: &Ident,
+        interner: &Expr,
+        scope: &mut PatternScope,
+    ) -> Vec<proc_macro2::TokenStream> {
+        self.bounds()
+            .map(|bound| {
+                let pat_tokens = bound_pattern_tokens(self.bounded_ty(), bound, ir_crate, scope);
+                let trait_name = bound.ident();
+                quote! {
+                    #ir_crate::primitives::WhereClause {
+                        trait_id: #trait_name,
+                        pattern: #ir_crate::patterns::ExactPatternSeq::new(
+                            #ir_crate::patterns::PatternSeq::new_trait_impl(
+                                #interner,
+                                &#pat_tokens,
+                                #trait_name
+                            ).unwrap()
+                        )
+                        .unwrap()
+                        .boxed(),
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl AssocTypeItem {
+    fn to_assoc_type_value_tokens(
+        &self,
+        ir_crate: &Ident,
+        interner: &Expr,
+        scope: &mut PatternScope,
+    ) -> proc_macro2::TokenStream {
+        let value = self.value_ty().to_pattern_tokens(ir_crate, scope).1;
+        let assoc_type_id = self.name();
+        quote! {
+            #ir_crate::primitives::AssocTypeValue {
+                assoc_type_id: #assoc_type_id,
+                value: #ir_crate::patterns::ExactPatternSeq::new(
+                    #ir_crate::patterns::PatternSeq::new(#interner, &[ #value ]).unwrap()
+                )
+                .unwrap()
+                .boxed(),
             }
         }
     }
 }
 
+/// Creates pattern tokens of a `bounded: r#trait` bound, which is also the layout of trait impls.
+fn bound_pattern_tokens(
+    bounded: &Type,
+    r#trait: &Path,
+    ir_crate: &Ident,
+    scope: &mut PatternScope,
+) -> proc_macro2::TokenStream {
+    let bounded = bounded.to_pattern_tokens(ir_crate, scope).1;
+    if let Some(args) = r#trait.args() {
+        let args = args
+            .iter()
+            .map(|arg| arg.to_pattern_tokens(ir_crate, scope).1);
+        quote! {
+            [ #bounded #( #args )* ]
+        }
+    } else {
+        quote! {
+            [ #bounded ]
+        }
+    }
+}
+
+#[allow(dead_code)]
 struct UseCrate {
     use_token: Token![use],
     crate_token: Token![crate],
@@ -51,6 +227,7 @@ impl Parse for UseCrate {
     }
 }
 
+#[allow(dead_code)]
 struct ImplPatternsInput {
     use_crate: Option<UseCrate>,
     interner_expr: Expr,
@@ -83,21 +260,17 @@ impl Parse for ImplPatternsInput {
     }
 }
 
-pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let ImplPatternsInput {
-        use_crate,
-        interner_expr: ref interner,
-        impls,
-        ..
-    } = parse_macro_input!(input as ImplPatternsInput);
-    let ir_crate = use_crate.map_or_else(
-        || Ident::new("crate", proc_macro2::Span::mixed_site()),
-        |use_crate| use_crate.crate_name,
-    );
-    let impls = impls.iter().map(|item| {
-        let primary_ctor = match item {
+impl Impl {
+    /// Creates tokens of an expression evaluating to `&PatternSeq` of the impl header.
+    fn header_tokens(
+        &self,
+        ir_crate: &Ident,
+        interner: &Expr,
+        scope: &mut PatternScope,
+    ) -> proc_macro2::TokenStream {
+        match self {
             Impl::Inherent(inherent) => {
-                let pat_tokens = inherent.to_pattern_tokens(&ir_crate);
+                let pat_tokens = inherent.to_pattern_tokens(ir_crate, scope);
                 quote! {
                     #ir_crate::patterns::PatternSeq::new(
                         #interner,
@@ -106,7 +279,7 @@ pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 }
             }
             Impl::Trait(tr) => {
-                let pat_tokens = tr.to_pattern_tokens(&ir_crate);
+                let pat_tokens = tr.to_pattern_tokens(ir_crate, scope);
                 let trait_name = tr.trait_name();
                 quote! {
                     #ir_crate::patterns::PatternSeq::new_trait_impl(
@@ -116,7 +289,28 @@ pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                     ).unwrap()
                 }
             }
-        };
+        }
+    }
+}
+
+fn ir_crate_ident(use_crate: Option<UseCrate>) -> Ident {
+    use_crate.map_or_else(
+        || Ident::new("crate", proc_macro2::Span::mixed_site()),
+        |use_crate| use_crate.crate_name,
+    )
+}
+
+pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ImplPatternsInput {
+        use_crate,
+        interner_expr: ref interner,
+        impls,
+        ..
+    } = parse_macro_input!(input as ImplPatternsInput);
+    let ir_crate = ir_crate_ident(use_crate);
+    let impls = impls.iter().map(|item| {
+        let scope = &mut PatternScope::default();
+        let primary_ctor = item.header_tokens(&ir_crate, interner, scope);
         if item.has_inference_vars() {
             quote! { #primary_ctor.boxed() }
         } else {
@@ -134,3 +328,55 @@ pub fn impl_patterns(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     }
     .into()
 }
+
+pub fn add_impls(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ImplPatternsInput {
+        use_crate,
+        interner_expr: ref interner,
+        impls,
+        ..
+    } = parse_macro_input!(input as ImplPatternsInput);
+    let ir_crate = ir_crate_ident(use_crate);
+    let impls = impls.iter().map(|item| {
+        let scope = &mut PatternScope::default();
+        let header = item.header_tokens(&ir_crate, interner, scope);
+        let header = quote! {
+            #ir_crate::patterns::ExactPatternSeq::new(#header)
+                .unwrap()
+                .boxed()
+        };
+        let where_clauses = item
+            .where_predicates()
+            .flat_map(|pred| pred.to_where_clause_tokens(&ir_crate, interner, scope))
+            .collect::<Vec<_>>();
+        let assoc_types = item
+            .assoc_types()
+            .iter()
+            .map(|assoc_type| assoc_type.to_assoc_type_value_tokens(&ir_crate, interner, scope));
+        match item {
+            Impl::Inherent(_) => quote! {
+                (#interner)
+                    .new_inherent_impl(#header, [ #( #where_clauses ),* ].into())
+                    .unwrap()
+            },
+            Impl::Trait(tr) => {
+                let trait_name = tr.trait_name();
+                quote! {
+                    (#interner).new_trait_impl(
+                        #trait_name,
+                        #header,
+                        [ #( #where_clauses ),* ].into(),
+                        [ #( #assoc_types ),* ].into()
+                    )
+                    .unwrap()
+                }
+            }
+        }
+    });
+    quote! {
+        (
+            #( #impls, )*
+        )
+    }
+    .into()
+}
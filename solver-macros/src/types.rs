@@ -1,95 +1,140 @@
 use crate::keywords;
 use syn::{
-    Ident, Token, bracketed, parenthesized,
+    Ident, LitInt, Token, braced, bracketed, parenthesized,
     parse::Parse,
     punctuated::Punctuated,
-    token::{Bracket, Paren},
+    token::{Brace, Bracket, Paren},
 };
 
 pub enum Type {
     Grouped(Grouped),
+    Tuple(Tuple),
     Never(Never),
     Placeholder(Placeholder),
     Inferred(Inferred),
     Slice(Slice),
+    Array(Array),
     Ref(Ref),
     RefMut(RefMut),
     RefDrop(RefDrop),
     Ptr(Ptr),
     PtrMut(PtrMut),
+    Projection(Projection),
+    FnPtr(FnPtr),
+    Dyn(Dyn),
     Path(Path),
+    Const(ConstArg),
 }
 
+#[allow(dead_code)]
 pub struct Grouped {
     braces: Paren,
     inner: Box<Type>,
 }
 
-impl Grouped {
-    pub fn inner_ty(&self) -> &Box<Type> {
-        &self.inner
-    }
+/// Tuple type, i.e. parenthesized types with at least one comma or none at all.
+#[allow(dead_code)]
+pub struct Tuple {
+    parens: Paren,
+    elements: Punctuated<Type, Token![,]>,
+}
+
+/// Function pointer `fn(Params) -> Ret`, where omitted return type means `()`.
+#[allow(dead_code)]
+pub struct FnPtr {
+    fn_token: Token![fn],
+    parens: Paren,
+    params: Punctuated<Type, Token![,]>,
+    ret: Option<(Token![->], Box<Type>)>,
 }
 
+/// Trait object `dyn Trait<Args>`.
+#[allow(dead_code)]
+pub struct Dyn {
+    dyn_token: Token![dyn],
+    r#trait: Path,
+}
+
+#[allow(dead_code)]
 pub struct Never(Token![!]);
 
+#[allow(dead_code)]
 pub struct Placeholder(Token![_]);
 
-pub struct Inferred(Token![?]);
+#[allow(dead_code)]
+pub struct Inferred(Token![?], Option<Ident>);
 
+#[allow(dead_code)]
 pub struct Slice {
     brackets: Bracket,
     inner: Box<Type>,
 }
 
-impl Slice {
-    pub fn element_ty(&self) -> &Box<Type> {
-        &self.inner
-    }
+#[allow(dead_code)]
+pub struct Array {
+    brackets: Bracket,
+    inner: Box<Type>,
+    semi_token: Token![;],
+    len: ConstArg,
 }
 
+/// Const generic argument: a value, a placeholder, an inference variable or a const generic
+/// parameter, which may be wrapped in braces.
+#[allow(dead_code)]
+pub enum ConstArg {
+    Value(LitInt, u128),
+    Placeholder(Placeholder),
+    Inferred(Inferred),
+    Param(Ident),
+    Braced(Brace, Box<ConstArg>),
+}
+
+#[allow(dead_code)]
 pub struct Ref {
     ref_token: Token![&],
     pointee: Box<Type>,
 }
 
+#[allow(dead_code)]
 pub struct RefMut {
     ref_token: Token![&],
     mut_token: Token![mut],
     pointee: Box<Type>,
 }
 
+#[allow(dead_code)]
 pub struct RefDrop {
     ref_token: Token![&],
     drop_token: keywords::drop,
     pointee: Box<Type>,
 }
 
+#[allow(dead_code)]
 pub struct Ptr {
     ptr_token: Token![*],
     pointee: Box<Type>,
 }
 
+#[allow(dead_code)]
 pub struct PtrMut {
     ptr_token: Token![*],
-    mut_token: keywords::drop,
+    mut_token: Token![mut],
     pointee: Box<Type>,
 }
 
-macro_rules! project_pointee_impl {
-    ($($ty:ident),+) => {
-        $(
-            impl $ty {
-                pub fn pointee_ty(&self) -> &Box<Type> {
-                    &self.pointee
-                }
-            }
-        )*
-    };
+/// Associated type projection `<Type as Trait<Args>>::Name`.
+#[allow(dead_code)]
+pub struct Projection {
+    lt_token: Token![<],
+    self_ty: Box<Type>,
+    as_token: Token![as],
+    r#trait: Path,
+    gt_token: Token![>],
+    colon2_token: Token![::],
+    name: Ident,
 }
 
-project_pointee_impl!(Ref, RefMut, RefDrop, Ptr, PtrMut);
-
+#[allow(dead_code)]
 pub struct GenericArgs {
     lt_token: Token![<],
     args: Punctuated<Type, Token![,]>,
@@ -124,7 +169,18 @@ mod parsing_impls {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
             let lookahead = input.lookahead1();
             if lookahead.peek(Paren) {
-                Ok(Self::Grouped(input.parse()?))
+                let content;
+                let parens = parenthesized!(content in input);
+                let elements = Punctuated::parse_terminated(&content)?;
+                if elements.len() == 1 && !elements.trailing_punct() {
+                    let inner = elements.into_iter().next().map(Box::new).unwrap();
+                    Ok(Self::Grouped(Grouped {
+                        braces: parens,
+                        inner,
+                    }))
+                } else {
+                    Ok(Self::Tuple(Tuple { parens, elements }))
+                }
             } else if lookahead.peek(Token![!]) {
                 Ok(Self::Never(input.parse()?))
             } else if lookahead.peek(Token![_]) {
@@ -132,7 +188,21 @@ mod parsing_impls {
             } else if lookahead.peek(Token![?]) {
                 Ok(Self::Inferred(input.parse()?))
             } else if lookahead.peek(Bracket) {
-                Ok(Self::Slice(input.parse()?))
+                let content;
+                let brackets = bracketed!(content in input);
+                let inner = content.parse()?;
+                if content.peek(Token![;]) {
+                    Ok(Self::Array(Array {
+                        brackets,
+                        inner,
+                        semi_token: content.parse()?,
+                        len: content.parse()?,
+                    }))
+                } else {
+                    Ok(Self::Slice(Slice { brackets, inner }))
+                }
+            } else if lookahead.peek(LitInt) || lookahead.peek(Brace) {
+                Ok(Self::Const(input.parse()?))
             } else if lookahead.peek(Token![&]) {
                 let ref_token = input.parse()?;
                 if input.peek(Token![mut]) {
@@ -167,6 +237,12 @@ mod parsing_impls {
                         pointee: input.parse()?,
                     }))
                 }
+            } else if lookahead.peek(Token![<]) {
+                Ok(Self::Projection(input.parse()?))
+            } else if lookahead.peek(Token![fn]) {
+                Ok(Self::FnPtr(input.parse()?))
+            } else if lookahead.peek(Token![dyn]) {
+                Ok(Self::Dyn(input.parse()?))
             } else if lookahead.peek(Ident) {
                 Ok(Self::Path(input.parse()?))
             } else {
@@ -175,40 +251,88 @@ mod parsing_impls {
         }
     }
 
-    impl Parse for Grouped {
+    impl Parse for Never {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-            let content;
-            Ok(Self {
-                braces: parenthesized!(content in input),
-                inner: content.parse()?,
-            })
+            input.parse::<Token![!]>().map(Self)
         }
     }
 
-    impl Parse for Never {
+    impl Parse for Placeholder {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-            input.parse::<Token![!]>().map(|token| Self(token))
+            input.parse::<Token![_]>().map(Self)
         }
     }
 
-    impl Parse for Placeholder {
+    impl Parse for Inferred {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-            input.parse::<Token![_]>().map(|token| Self(token))
+            Ok(Self(
+                input.parse()?,
+                if input.peek(Ident) {
+                    Some(input.parse()?)
+                } else {
+                    None
+                },
+            ))
         }
     }
 
-    impl Parse for Inferred {
+    impl Parse for ConstArg {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-            Ok(Self(input.parse()?))
+            let lookahead = input.lookahead1();
+            if lookahead.peek(LitInt) {
+                let lit: LitInt = input.parse()?;
+                let value = lit.base10_parse()?;
+                Ok(Self::Value(lit, value))
+            } else if lookahead.peek(Token![_]) {
+                Ok(Self::Placeholder(input.parse()?))
+            } else if lookahead.peek(Token![?]) {
+                Ok(Self::Inferred(input.parse()?))
+            } else if lookahead.peek(Ident) {
+                Ok(Self::Param(input.parse()?))
+            } else if lookahead.peek(Brace) {
+                let content;
+                Ok(Self::Braced(braced!(content in input), content.parse()?))
+            } else {
+                Err(lookahead.error())
+            }
         }
     }
 
-    impl Parse for Slice {
+    impl Parse for FnPtr {
         fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
             let content;
             Ok(Self {
-                brackets: bracketed!(content in input),
-                inner: content.parse()?,
+                fn_token: input.parse()?,
+                parens: parenthesized!(content in input),
+                params: Punctuated::parse_terminated(&content)?,
+                ret: if input.peek(Token![->]) {
+                    Some((input.parse()?, input.parse()?))
+                } else {
+                    None
+                },
+            })
+        }
+    }
+
+    impl Parse for Dyn {
+        fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+            Ok(Self {
+                dyn_token: input.parse()?,
+                r#trait: input.parse()?,
+            })
+        }
+    }
+
+    impl Parse for Projection {
+        fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+            Ok(Self {
+                lt_token: input.parse()?,
+                self_ty: input.parse()?,
+                as_token: input.parse()?,
+                r#trait: input.parse()?,
+                gt_token: input.parse()?,
+                colon2_token: input.parse()?,
+                name: input.parse()?,
             })
         }
     }
@@ -254,47 +378,67 @@ mod parsing_impls {
 
 mod pattern_impls {
     use super::*;
-    use crate::patterns::ToPatternTokens;
+    use crate::patterns::{ElementKind, PatternScope, ToPatternTokens};
     use phf::phf_map;
     use quote::{TokenStreamExt as _, quote};
 
     impl ToPatternTokens for Type {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
             match self {
-                Type::Grouped(braced) => braced.to_pattern_tokens(ir_crate),
-                Type::Never(never) => never.to_pattern_tokens(ir_crate),
-                Type::Placeholder(placeholder) => placeholder.to_pattern_tokens(ir_crate),
-                Type::Inferred(inferred) => inferred.to_pattern_tokens(ir_crate),
-                Type::Slice(slice) => slice.to_pattern_tokens(ir_crate),
-                Type::Ref(reference) => reference.to_pattern_tokens(ir_crate),
-                Type::RefMut(ref_mut) => ref_mut.to_pattern_tokens(ir_crate),
-                Type::RefDrop(ref_drop) => ref_drop.to_pattern_tokens(ir_crate),
-                Type::Ptr(ptr) => ptr.to_pattern_tokens(ir_crate),
-                Type::PtrMut(ptr_mut) => ptr_mut.to_pattern_tokens(ir_crate),
-                Type::Path(path) => path.to_pattern_tokens(ir_crate),
+                Type::Grouped(braced) => braced.to_pattern_tokens(ir_crate, scope),
+                Type::Tuple(tuple) => tuple.to_pattern_tokens(ir_crate, scope),
+                Type::Never(never) => never.to_pattern_tokens(ir_crate, scope),
+                Type::Placeholder(placeholder) => placeholder.to_pattern_tokens(ir_crate, scope),
+                Type::Inferred(inferred) => inferred.to_pattern_tokens(ir_crate, scope),
+                Type::Slice(slice) => slice.to_pattern_tokens(ir_crate, scope),
+                Type::Array(array) => array.to_pattern_tokens(ir_crate, scope),
+                Type::Ref(reference) => reference.to_pattern_tokens(ir_crate, scope),
+                Type::RefMut(ref_mut) => ref_mut.to_pattern_tokens(ir_crate, scope),
+                Type::RefDrop(ref_drop) => ref_drop.to_pattern_tokens(ir_crate, scope),
+                Type::Ptr(ptr) => ptr.to_pattern_tokens(ir_crate, scope),
+                Type::PtrMut(ptr_mut) => ptr_mut.to_pattern_tokens(ir_crate, scope),
+                Type::Projection(projection) => projection.to_pattern_tokens(ir_crate, scope),
+                Type::FnPtr(fn_ptr) => fn_ptr.to_pattern_tokens(ir_crate, scope),
+                Type::Dyn(dyn_ty) => dyn_ty.to_pattern_tokens(ir_crate, scope),
+                Type::Path(path) => path.to_pattern_tokens(ir_crate, scope),
+                Type::Const(arg) => arg.to_pattern_tokens(ir_crate, scope),
             }
         }
 
         fn has_inference_vars(&self) -> bool {
             match self {
                 Type::Grouped(braced) => braced.has_inference_vars(),
+                Type::Tuple(tuple) => tuple.has_inference_vars(),
                 Type::Never(never) => never.has_inference_vars(),
                 Type::Placeholder(placeholder) => placeholder.has_inference_vars(),
                 Type::Inferred(inferred) => inferred.has_inference_vars(),
                 Type::Slice(slice) => slice.has_inference_vars(),
+                Type::Array(array) => array.has_inference_vars(),
                 Type::Ref(reference) => reference.has_inference_vars(),
                 Type::RefMut(ref_mut) => ref_mut.has_inference_vars(),
                 Type::RefDrop(ref_drop) => ref_drop.has_inference_vars(),
                 Type::Ptr(ptr) => ptr.has_inference_vars(),
                 Type::PtrMut(ptr_mut) => ptr_mut.has_inference_vars(),
+                Type::Projection(projection) => projection.has_inference_vars(),
+                Type::FnPtr(fn_ptr) => fn_ptr.has_inference_vars(),
+                Type::Dyn(dyn_ty) => dyn_ty.has_inference_vars(),
                 Type::Path(path) => path.has_inference_vars(),
+                Type::Const(arg) => arg.has_inference_vars(),
             }
         }
     }
 
     impl ToPatternTokens for Grouped {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            self.inner.to_pattern_tokens(ir_crate)
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            self.inner.to_pattern_tokens(ir_crate, scope)
         }
 
         fn has_inference_vars(&self) -> bool {
@@ -302,8 +446,42 @@ mod pattern_impls {
         }
     }
 
+    impl ToPatternTokens for Tuple {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (mut len, mut tokens) = (0, proc_macro2::TokenStream::new());
+            tokens.append_all(self.elements.iter().map(|ty| {
+                let (elem_len, elem_tokens) = ty.to_pattern_tokens(ir_crate, scope);
+                len += elem_len;
+                elem_tokens
+            }));
+            let arity = self.elements.len();
+            (
+                len + 1,
+                quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: #len,
+                        type_id: #ir_crate::primitives::TypeId::Tuple(#arity),
+                    },
+                    #tokens
+                },
+            )
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            self.elements.iter().any(|ty| ty.has_inference_vars())
+        }
+    }
+
     impl ToPatternTokens for Never {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            _scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
             (
                 1,
                 quote! {
@@ -321,11 +499,18 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for Placeholder {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let placeholder = scope.new_placeholder();
             (
                 1,
                 quote! {
-                    #ir_crate::patterns::PatternElement::TypePlaceholder,
+                    #ir_crate::patterns::PatternElement::TypePlaceholder(
+                        #ir_crate::patterns::PlaceholderIndex(#placeholder)
+                    ),
                 },
             )
         }
@@ -335,14 +520,23 @@ mod pattern_impls {
         }
     }
 
+    impl Inferred {
+        fn inference_var(&self, scope: &mut PatternScope) -> u32 {
+            match &self.1 {
+                Some(name) => scope.named_inference_var(name),
+                None => scope.new_inference_var(),
+            }
+        }
+    }
+
     impl ToPatternTokens for Inferred {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            (
-                1,
-                quote! {
-                    #ir_crate::patterns::PatternElement::InferredType,
-                },
-            )
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let var = self.inference_var(scope);
+            (1, ElementKind::Type.inference_var_tokens(ir_crate, var))
         }
 
         fn has_inference_vars(&self) -> bool {
@@ -351,8 +545,12 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for Slice {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.inner.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.inner.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -370,9 +568,93 @@ mod pattern_impls {
         }
     }
 
+    impl ToPatternTokens for Array {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (inner_len, inner_tokens) = self.inner.to_pattern_tokens(ir_crate, scope);
+            let (len_len, len_tokens) = self.len.to_pattern_tokens(ir_crate, scope);
+            let len = inner_len + len_len;
+            (
+                len + 1,
+                quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: #len,
+                        type_id: #ir_crate::primitives::TypeId::Array,
+                    },
+                    #inner_tokens
+                    #len_tokens
+                },
+            )
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            self.inner.has_inference_vars() || self.len.has_inference_vars()
+        }
+    }
+
+    impl ToPatternTokens for ConstArg {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            match self {
+                ConstArg::Value(_, value) => (
+                    1,
+                    quote! {
+                        #ir_crate::patterns::PatternElement::ConstValue(
+                            #ir_crate::primitives::ConstValue(#value)
+                        ),
+                    },
+                ),
+                ConstArg::Placeholder(_) => {
+                    let placeholder = scope.new_placeholder();
+                    (
+                        1,
+                        ElementKind::Const.placeholder_tokens(ir_crate, placeholder),
+                    )
+                }
+                ConstArg::Inferred(inferred) => {
+                    let var = inferred.inference_var(scope);
+                    (1, ElementKind::Const.inference_var_tokens(ir_crate, var))
+                }
+                ConstArg::Param(ident) => match scope.generic_param(ident) {
+                    Some((placeholder, ElementKind::Const)) => (
+                        1,
+                        ElementKind::Const.placeholder_tokens(ir_crate, placeholder),
+                    ),
+                    Some((_, ElementKind::Type)) | None => (
+                        1,
+                        syn::Error::new(
+                            ident.span(),
+                            format!("`{}` is not a const generic parameter", ident),
+                        )
+                        .to_compile_error(),
+                    ),
+                },
+                ConstArg::Braced(_, inner) => inner.to_pattern_tokens(ir_crate, scope),
+            }
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            match self {
+                ConstArg::Value(..) | ConstArg::Placeholder(_) | ConstArg::Param(_) => false,
+                ConstArg::Inferred(_) => true,
+                ConstArg::Braced(_, inner) => inner.has_inference_vars(),
+            }
+        }
+    }
+
     impl ToPatternTokens for Ref {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -391,8 +673,12 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for RefMut {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -413,8 +699,12 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for RefDrop {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -435,8 +725,12 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for Ptr {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -455,8 +749,12 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for PtrMut {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
-            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate);
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (len, tokens) = self.pointee.to_pattern_tokens(ir_crate, scope);
             (
                 len + 1,
                 quote! {
@@ -476,6 +774,117 @@ mod pattern_impls {
         }
     }
 
+    impl ToPatternTokens for FnPtr {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (mut len, mut tokens) = (0, proc_macro2::TokenStream::new());
+            tokens.append_all(self.params.iter().map(|ty| {
+                let (param_len, param_tokens) = ty.to_pattern_tokens(ir_crate, scope);
+                len += param_len;
+                param_tokens
+            }));
+            if let Some((_, ret)) = &self.ret {
+                let (ret_len, ret_tokens) = ret.to_pattern_tokens(ir_crate, scope);
+                len += ret_len;
+                tokens.append_all(ret_tokens);
+            } else {
+                len += 1;
+                tokens.append_all(quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: 0,
+                        type_id: #ir_crate::primitives::TypeId::Tuple(0),
+                    },
+                });
+            }
+            let arity = self.params.len();
+            (
+                len + 1,
+                quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: #len,
+                        type_id: #ir_crate::primitives::TypeId::FnPtr(#arity),
+                    },
+                    #tokens
+                },
+            )
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            self.params.iter().any(|ty| ty.has_inference_vars())
+                || self
+                    .ret
+                    .as_ref()
+                    .is_some_and(|(_, ret)| ret.has_inference_vars())
+        }
+    }
+
+    impl ToPatternTokens for Dyn {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (mut len, mut tokens) = (0, proc_macro2::TokenStream::new());
+            if let Some(args) = &self.r#trait.generic_args {
+                tokens.append_all(args.iter().map(|ty| {
+                    let (arg_len, arg_tokens) = ty.to_pattern_tokens(ir_crate, scope);
+                    len += arg_len;
+                    arg_tokens
+                }));
+            }
+            let trait_id = &self.r#trait.ident;
+            (
+                len + 1,
+                quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: #len,
+                        type_id: #ir_crate::primitives::TypeId::Dyn(#trait_id),
+                    },
+                    #tokens
+                },
+            )
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            self.r#trait.has_inference_vars()
+        }
+    }
+
+    impl ToPatternTokens for Projection {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            let (mut len, mut tokens) = self.self_ty.to_pattern_tokens(ir_crate, scope);
+            if let Some(args) = &self.r#trait.generic_args {
+                tokens.append_all(args.iter().map(|ty| {
+                    let (arg_len, arg_tokens) = ty.to_pattern_tokens(ir_crate, scope);
+                    len += arg_len;
+                    arg_tokens
+                }));
+            }
+            let assoc_type_id = &self.name;
+            (
+                len + 1,
+                quote! {
+                    #ir_crate::patterns::PatternElement::TypeConstructor {
+                        args_length: #len,
+                        type_id: #ir_crate::primitives::TypeId::Projection(#assoc_type_id),
+                    },
+                    #tokens
+                },
+            )
+        }
+
+        fn has_inference_vars(&self) -> bool {
+            self.self_ty.has_inference_vars() || self.r#trait.has_inference_vars()
+        }
+    }
+
     #[allow(non_camel_case_types)]
     enum ReservedType {
         NonZero,
@@ -604,11 +1013,20 @@ mod pattern_impls {
     }
 
     impl ToPatternTokens for Path {
-        fn to_pattern_tokens(&self, ir_crate: &Ident) -> (usize, proc_macro2::TokenStream) {
+        fn to_pattern_tokens(
+            &self,
+            ir_crate: &Ident,
+            scope: &mut PatternScope,
+        ) -> (usize, proc_macro2::TokenStream) {
+            if self.generic_args.is_none()
+                && let Some((placeholder, kind)) = scope.generic_param(&self.ident)
+            {
+                return (1, kind.placeholder_tokens(ir_crate, placeholder));
+            }
             let (mut len, mut tokens) = (0, proc_macro2::TokenStream::new());
             if let Some(args) = &self.generic_args {
                 tokens.append_all(args.iter().map(|ty| {
-                    let (arg_len, arg_tokens) = ty.to_pattern_tokens(ir_crate);
+                    let (arg_len, arg_tokens) = ty.to_pattern_tokens(ir_crate, scope);
                     len += arg_len;
                     arg_tokens
                 }));
@@ -1,20 +1,131 @@
 use solver_ir::{
-    interner::{AdtId, GenericArg, Interner, ItemId, TraitId},
-    primitives::{AdtData, GenericArgData, GenericArgType, TraitData, TypeData},
+    discrimination_tree::DiscriminationTree,
+    interner::{AdtId, AssocTypeId, GenericArg, ImplId, Interner, ItemId, TraitId},
+    patterns::{ExactPatternSeq, PatternError, PatternSeq},
+    primitives::{
+        AdtData, AssocTypeData, AssocTypeValue, GenericArgData, GenericArgType, ImplData,
+        TraitData, TypeData, WhereClause,
+    },
+};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    hash::{BuildHasher, Hash, Hasher, RandomState},
+    num::NonZero,
+    ops::Deref,
+    sync::{Mutex, RwLock},
 };
-use std::{cell::UnsafeCell, num::NonZero};
+
+/// Reference to deduplicated data, which is compared and hashed by address.
+#[derive(Debug)]
+pub struct Interned<'a, T: ?Sized>(&'a T);
+
+impl<T: ?Sized> Clone for Interned<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Interned<'_, T> {}
+
+impl<T: ?Sized> PartialEq for Interned<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.0, other.0)
+    }
+}
+
+impl<T: ?Sized> Eq for Interned<'_, T> {}
+
+impl<T: ?Sized> Hash for Interned<'_, T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(self.0, state)
+    }
+}
+
+impl<T: ?Sized> Deref for Interned<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
 
 #[derive(Debug)]
 enum InternerItem {
     Adt(AdtData),
-    Trait(TraitData),
+    // Lifetime of the interner is erased the same way as for `Impl`
+    Trait(TraitData<&'static NaiveInterner>),
+    // Lifetime of the interner is erased here, because impls can't borrow the interner that owns
+    // them. See `new_impl` for safety.
+    Impl(ImplData<&'static NaiveInterner>),
+    // Lifetime of the interner is erased the same way as for `Impl`
+    AssocType(AssocTypeData<&'static NaiveInterner>),
+}
+
+const INTERN_SET_SHARDS: usize = 16;
+
+/// Set of deduplicated values, which is split into shards by hash of the values so that threads
+/// interning different values rarely wait for each other.
+#[derive(Debug)]
+struct InternSet<T: ?Sized> {
+    hasher: RandomState,
+    shards: [Mutex<HashSet<Box<T>>>; INTERN_SET_SHARDS],
 }
 
+impl<T: ?Sized> Default for InternSet<T> {
+    fn default() -> Self {
+        Self {
+            hasher: RandomState::new(),
+            shards: std::array::from_fn(|_| Mutex::default()),
+        }
+    }
+}
+
+impl<T: ?Sized + Eq + Hash> InternSet<T> {
+    /// Returns address of the value that is equal to `data`, inserting `data` if there is no such
+    /// value yet.
+    ///
+    /// Values are boxed and never removed, so the address stays valid as long as the set.
+    fn intern<B>(&self, data: B) -> *const T
+    where
+        B: Borrow<T> + Into<Box<T>>,
+    {
+        let shard = self.hasher.hash_one(data.borrow()) as usize % INTERN_SET_SHARDS;
+        let mut set = self.shards[shard].lock().unwrap();
+        if let Some(interned) = set.get(data.borrow()) {
+            return &**interned;
+        }
+        let data = data.into();
+        let interned: *const T = &*data;
+        set.insert(data);
+        interned
+    }
+}
+
+/// Interner that can be shared between threads.
+///
+/// Items and interned data are boxed and only ever appended, so references to them stay valid
+/// after locks protecting the storage are released.
 #[derive(Debug, Default)]
 pub struct NaiveInterner {
-    items: UnsafeCell<Vec<Box<InternerItem>>>,
+    // Items are boxed so that references to them stay valid when `items` grows
+    #[allow(clippy::vec_box)]
+    items: RwLock<Vec<Box<InternerItem>>>,
+    // Headers of trait impls by id of the trait. Lifetime of the interner is erased the same way
+    // as for `InternerItem::Impl`.
+    impl_index: RwLock<ImplIndex>,
+    // Deduplicated types, generic args and substitutions. Lifetime of the interner is erased the
+    // same way as for `InternerItem::Impl`, see `intern_type` for safety.
+    types: InternSet<TypeData<&'static NaiveInterner>>,
+    generic_args: InternSet<GenericArgData<&'static NaiveInterner>>,
+    substitutions: InternSet<[GenericArg<&'static NaiveInterner>]>,
 }
 
+type ImplIndex = HashMap<
+    TraitId<&'static NaiveInterner>,
+    DiscriminationTree<&'static NaiveInterner, ImplId<&'static NaiveInterner>>,
+>;
+
 impl NaiveInterner {
     pub fn new() -> Self {
         Self::default()
@@ -33,57 +144,269 @@ impl NaiveInterner {
         }
     }
 
+    fn get_item(&self, id: ItemId) -> &InternerItem {
+        let items = self.items.read().unwrap();
+        let item: *const InternerItem = &*items[Self::into_items_index(id)];
+        // Safe because items are boxed and never removed or mutated, so they live as long as
+        // `self` even after the lock is released
+        unsafe { &*item }
+    }
+
+    fn push_item(&self, item: InternerItem) -> ItemId {
+        let mut items = self.items.write().unwrap();
+        items.push(Box::new(item));
+        Self::new_item_id(items.len() - 1)
+    }
+
     pub fn get_adt(&self, id: AdtId<&Self>) -> &AdtData {
-        // Safe because we are single threaded and references to `self.items` do not live longer
-        // than any of our methods (methods do not invoke each other)
-        let items = unsafe { &*self.items.get() };
-        match &*items[Self::into_items_index(*id)] {
+        match self.get_item(*id) {
             InternerItem::Adt(data) => data,
             _ => unreachable!(),
         }
     }
 
     pub fn new_adt(&self, name: Box<str>, generic_args: Box<[GenericArgType]>) -> AdtId<&Self> {
-        // See `get_adt` for safety
-        let items = unsafe { &mut *self.items.get() };
-        let id = AdtId::new(Self::new_item_id(items.len()));
-        items.push(Box::new(InternerItem::Adt(AdtData {
-            name,
-            generic_args,
-        })));
-        id
+        AdtId::new(self.push_item(InternerItem::Adt(AdtData { name, generic_args })))
     }
 
-    pub fn get_trait(&self, id: TraitId<&Self>) -> &TraitData {
-        // See `get_adt` for safety
-        let items = unsafe { &*self.items.get() };
-        match &*items[Self::into_items_index(*id)] {
+    pub fn get_trait(&self, id: TraitId<&Self>) -> &TraitData<&Self> {
+        match self.get_item(*id) {
             InternerItem::Trait(data) => data,
             _ => unreachable!(),
         }
     }
 
-    pub fn new_trait(&self, name: Box<str>, generic_args: Box<[GenericArgType]>) -> TraitId<&Self> {
-        // See `get_adt` for safety
-        let items = unsafe { &mut *self.items.get() };
-        let id = TraitId::new(Self::new_item_id(items.len()));
-        items.push(Box::new(InternerItem::Trait(TraitData {
+    /// Adds trait after checking that its supertraits are valid bounds of the trait, see
+    /// `WhereClause::check_trait_bound`.
+    pub fn new_trait(
+        &self,
+        name: Box<str>,
+        generic_args: Box<[GenericArgType]>,
+        supertraits: Box<[WhereClause<&Self>]>,
+    ) -> Result<TraitId<&Self>, PatternError> {
+        for supertrait in &supertraits {
+            supertrait.check_trait_bound(self, &generic_args)?;
+        }
+        let data = TraitData {
             name,
             generic_args,
-        })));
-        id
+            supertraits,
+        };
+        // See `new_impl` for safety
+        let data =
+            unsafe { std::mem::transmute::<TraitData<&Self>, TraitData<&'static Self>>(data) };
+        Ok(TraitId::new(self.push_item(InternerItem::Trait(data))))
+    }
+
+    pub fn get_assoc_type(&self, id: AssocTypeId<&Self>) -> &AssocTypeData<&Self> {
+        match self.get_item(*id) {
+            InternerItem::AssocType(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn new_assoc_type(&self, trait_id: TraitId<&Self>, name: Box<str>) -> AssocTypeId<&Self> {
+        let data = AssocTypeData { name, trait_id };
+        // See `new_impl` for safety
+        let data = unsafe {
+            std::mem::transmute::<AssocTypeData<&Self>, AssocTypeData<&'static Self>>(data)
+        };
+        AssocTypeId::new(self.push_item(InternerItem::AssocType(data)))
+    }
+
+    pub fn get_impl(&self, id: ImplId<&Self>) -> &ImplData<&Self> {
+        match self.get_item(*id) {
+            InternerItem::Impl(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Adds impl of `trait_id` after checking that `pattern` is an implementor type followed by
+    /// generic arguments of the trait, see `PatternSeq::new_trait_impl`.
+    pub fn new_trait_impl(
+        &self,
+        trait_id: TraitId<&Self>,
+        pattern: Box<ExactPatternSeq<&Self>>,
+        where_clauses: Box<[WhereClause<&Self>]>,
+        assoc_types: Box<[AssocTypeValue<&Self>]>,
+    ) -> Result<ImplId<&Self>, PatternError> {
+        PatternSeq::new_trait_impl(self, &pattern, trait_id)?;
+        Ok(self.new_impl(ImplData {
+            trait_id: Some(trait_id),
+            pattern,
+            where_clauses,
+            assoc_types,
+        }))
+    }
+
+    /// Adds inherent impl after checking that `pattern` is a single implementor type, see
+    /// `PatternSeq::new_inherent_impl`.
+    pub fn new_inherent_impl(
+        &self,
+        pattern: Box<ExactPatternSeq<&Self>>,
+        where_clauses: Box<[WhereClause<&Self>]>,
+    ) -> Result<ImplId<&Self>, PatternError> {
+        PatternSeq::new_inherent_impl(self, &pattern)?;
+        Ok(self.new_impl(ImplData {
+            trait_id: None,
+            pattern,
+            where_clauses,
+            assoc_types: [].into(),
+        }))
+    }
+
+    fn new_impl(&self, data: ImplData<&Self>) -> ImplId<&Self> {
+        // Safe because `ImplData` refers to the interner only through ids, which don't borrow
+        // anything, and `get_impl` shortens the lifetime back to the one of `self`
+        let data = unsafe { std::mem::transmute::<ImplData<&Self>, ImplData<&'static Self>>(data) };
+        // Lock of `items` is held while the index is updated, so readers can't see an impl that is
+        // missing from the index
+        let mut items = self.items.write().unwrap();
+        let item_id = Self::new_item_id(items.len());
+        if let Some(trait_id) = data.trait_id {
+            let mut impl_index = self.impl_index.write().unwrap();
+            let tree = impl_index.entry(trait_id).or_default();
+            tree.insert(&data.pattern, ImplId::new(item_id));
+        }
+        items.push(Box::new(InternerItem::Impl(data)));
+        ImplId::new(item_id)
+    }
+
+    fn impls_by(&self, mut pred: impl FnMut(&ImplData<&Self>) -> bool) -> Vec<ImplId<&Self>> {
+        let items = self.items.read().unwrap();
+        items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| match &**item {
+                InternerItem::Impl(data) if pred(data) => {
+                    Some(ImplId::new(Self::new_item_id(index)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn item_ids_by(&self, mut pred: impl FnMut(&InternerItem) -> bool) -> Vec<ItemId> {
+        let items = self.items.read().unwrap();
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| pred(item))
+            .map(|(index, _)| Self::new_item_id(index))
+            .collect()
+    }
+
+    pub fn get_adts(&self) -> Vec<AdtId<&Self>> {
+        self.item_ids_by(|item| matches!(item, InternerItem::Adt(_)))
+            .into_iter()
+            .map(AdtId::new)
+            .collect()
+    }
+
+    pub fn get_traits(&self) -> Vec<TraitId<&Self>> {
+        self.item_ids_by(|item| matches!(item, InternerItem::Trait(_)))
+            .into_iter()
+            .map(TraitId::new)
+            .collect()
+    }
+
+    pub fn get_trait_assoc_types(&self, trait_id: TraitId<&Self>) -> Vec<AssocTypeId<&Self>> {
+        self.item_ids_by(|item| match item {
+            InternerItem::AssocType(data) => data.trait_id.0 == trait_id.0,
+            _ => false,
+        })
+        .into_iter()
+        .map(AssocTypeId::new)
+        .collect()
+    }
+
+    pub fn get_trait_impls(&self, trait_id: TraitId<&Self>) -> Vec<ImplId<&Self>> {
+        self.impls_by(|data| data.trait_id.is_some_and(|id| id == trait_id))
+    }
+
+    pub fn get_candidate_impls(
+        &self,
+        trait_id: TraitId<&Self>,
+        pattern: &PatternSeq<&Self>,
+    ) -> Vec<ImplId<&Self>> {
+        let impl_index = self.impl_index.read().unwrap();
+        match impl_index.get(&TraitId::new(*trait_id)) {
+            Some(tree) => tree.candidates(pattern).into_iter().copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_inherent_impls(&self) -> Vec<ImplId<&Self>> {
+        self.impls_by(|data| data.trait_id.is_none())
+    }
+
+    pub fn intern_type(&self, data: TypeData<&Self>) -> Interned<'_, TypeData<&Self>> {
+        // Safe because `TypeData` refers to the interner only through ids and handles of interned
+        // data, which is never removed, so it lives as long as `self`
+        let data = unsafe { std::mem::transmute::<TypeData<&Self>, TypeData<&'static Self>>(data) };
+        let interned = self.types.intern(data);
+        // Safe because interned data is boxed and never removed, see above for lifetime
+        Interned(unsafe {
+            std::mem::transmute::<&TypeData<&'static Self>, &TypeData<&Self>>(&*interned)
+        })
+    }
+
+    pub fn intern_generic_arg(
+        &self,
+        data: GenericArgData<&Self>,
+    ) -> Interned<'_, GenericArgData<&Self>> {
+        // See `intern_type` for safety
+        let data = unsafe {
+            std::mem::transmute::<GenericArgData<&Self>, GenericArgData<&'static Self>>(data)
+        };
+        let interned = self.generic_args.intern(data);
+        // See `intern_type` for safety
+        Interned(unsafe {
+            std::mem::transmute::<&GenericArgData<&'static Self>, &GenericArgData<&Self>>(
+                &*interned,
+            )
+        })
+    }
+
+    pub fn intern_substitution(
+        &self,
+        args: Box<[GenericArg<&Self>]>,
+    ) -> Interned<'_, [GenericArg<&Self>]> {
+        // See `intern_type` for safety
+        let args = unsafe {
+            std::mem::transmute::<Box<[GenericArg<&Self>]>, Box<[GenericArg<&'static Self>]>>(args)
+        };
+        let interned = self.substitutions.intern(args);
+        // See `intern_type` for safety
+        Interned(unsafe {
+            std::mem::transmute::<&[GenericArg<&'static Self>], &[GenericArg<&Self>]>(&*interned)
+        })
     }
 }
 
 impl<'a> Interner for &'a NaiveInterner {
-    type InternedType = Box<TypeData<Self>>;
-    type InternedGenericArg = GenericArgData<Self>;
-    type InternedSubstitution = Box<[GenericArg<Self>]>;
+    type InternedType = Interned<'a, TypeData<Self>>;
+    type InternedGenericArg = Interned<'a, GenericArgData<Self>>;
+    type InternedSubstitution = Interned<'a, [GenericArg<Self>]>;
     type InternedAdtData = &'a AdtData;
-    type InternedTraitData = &'a TraitData;
+    type InternedTraitData = &'a TraitData<Self>;
+    type InternedImplData = &'a ImplData<Self>;
+    type InternedAssocTypeData = &'a AssocTypeData<Self>;
+
+    fn intern_type(self, data: TypeData<Self>) -> Self::InternedType {
+        NaiveInterner::intern_type(self, data)
+    }
+
+    fn intern_generic_arg(self, data: GenericArgData<Self>) -> Self::InternedGenericArg {
+        NaiveInterner::intern_generic_arg(self, data)
+    }
+
+    fn intern_substitution(self, args: Box<[GenericArg<Self>]>) -> Self::InternedSubstitution {
+        NaiveInterner::intern_substitution(self, args)
+    }
 
     fn type_data(self, ty: &Self::InternedType) -> &TypeData<Self> {
-        &*ty
+        ty
     }
 
     fn generic_arg_data(self, arg: &Self::InternedGenericArg) -> &GenericArgData<Self> {
@@ -91,17 +414,25 @@ impl<'a> Interner for &'a NaiveInterner {
     }
 
     fn substitution_data(self, subst: &Self::InternedSubstitution) -> &[GenericArg<Self>] {
-        &*subst
+        subst
     }
 
     fn adt_data(self, adt: &Self::InternedAdtData) -> &AdtData {
         adt
     }
 
-    fn trait_data(self, r#trait: &Self::InternedTraitData) -> &TraitData {
+    fn trait_data(self, r#trait: &Self::InternedTraitData) -> &TraitData<Self> {
         r#trait
     }
 
+    fn impl_data(self, r#impl: &Self::InternedImplData) -> &ImplData<Self> {
+        r#impl
+    }
+
+    fn assoc_type_data(self, assoc_type: &Self::InternedAssocTypeData) -> &AssocTypeData<Self> {
+        assoc_type
+    }
+
     fn get_adt_by_id(self, id: AdtId<Self>) -> Self::InternedAdtData {
         self.get_adt(id)
     }
@@ -109,4 +440,156 @@ impl<'a> Interner for &'a NaiveInterner {
     fn get_trait_by_id(self, id: TraitId<Self>) -> Self::InternedTraitData {
         self.get_trait(id)
     }
+
+    fn get_impl_by_id(self, id: ImplId<Self>) -> Self::InternedImplData {
+        self.get_impl(id)
+    }
+
+    fn get_assoc_type_by_id(self, id: AssocTypeId<Self>) -> Self::InternedAssocTypeData {
+        self.get_assoc_type(id)
+    }
+
+    fn adts(self) -> Vec<AdtId<Self>> {
+        self.get_adts()
+    }
+
+    fn traits(self) -> Vec<TraitId<Self>> {
+        self.get_traits()
+    }
+
+    fn trait_assoc_types(self, trait_id: TraitId<Self>) -> Vec<AssocTypeId<Self>> {
+        self.get_trait_assoc_types(trait_id)
+    }
+
+    fn trait_impls(self, trait_id: TraitId<Self>) -> Vec<ImplId<Self>> {
+        self.get_trait_impls(trait_id)
+    }
+
+    fn candidate_impls(
+        self,
+        trait_id: TraitId<Self>,
+        pattern: &PatternSeq<Self>,
+    ) -> Vec<ImplId<Self>> {
+        self.get_candidate_impls(trait_id, pattern)
+    }
+
+    fn inherent_impls(self) -> Vec<ImplId<Self>> {
+        self.get_inherent_impls()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solver_ir::{
+        add_items,
+        interner::{Substitution, Type},
+        patterns::{PatternElement, PlaceholderIndex},
+        primitives::ConstValue,
+    };
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn impl_headers_are_validated_before_they_are_stored() {
+        let interner = NaiveInterner::new();
+        let (_B, PartialEq) = add_items!(interner, {
+            struct B<T>;
+            trait PartialEq<Rhs>;
+        });
+        let placeholder = PatternElement::TypePlaceholder(PlaceholderIndex(0));
+        let header = |elems: &[_]| {
+            let pattern = PatternSeq::new(&interner, elems).unwrap();
+            ExactPatternSeq::new(pattern).unwrap().boxed()
+        };
+        assert_eq!(
+            interner
+                .new_trait_impl(PartialEq, header(&[placeholder]), [].into(), [].into())
+                .unwrap_err(),
+            PatternError::TraitArgCount {
+                index: 1,
+                expected: 1,
+                found: 0,
+            }
+        );
+        assert_eq!(
+            interner
+                .new_inherent_impl(header(&[placeholder, placeholder]), [].into())
+                .unwrap_err(),
+            PatternError::TrailingElements { index: 1 }
+        );
+        assert!(interner.get_trait_impls(PartialEq).is_empty());
+        assert!(interner.get_inherent_impls().is_empty());
+        interner
+            .new_trait_impl(
+                PartialEq,
+                header(&[placeholder, placeholder]),
+                [].into(),
+                [].into(),
+            )
+            .unwrap();
+        assert_eq!(interner.get_trait_impls(PartialEq).len(), 1);
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn structurally_equal_data_shares_one_handle() {
+        let interner = NaiveInterner::new();
+        let (A, B) = add_items!(interner, {
+            struct A;
+            struct B<T>;
+        });
+        let interner = &interner;
+        let a = || Type::adt(interner, A, []);
+        let b = |ty| Type::adt(interner, B, [GenericArg::ty(interner, ty)]);
+        assert_eq!(b(a()), b(a()));
+        assert_ne!(b(a()), b(b(a())));
+        assert_ne!(b(a()), Type::unit(interner));
+        assert_eq!(GenericArg::ty(interner, a()), GenericArg::ty(interner, a()));
+        assert_eq!(
+            GenericArg::constant(interner, ConstValue(3)),
+            GenericArg::constant(interner, ConstValue(3))
+        );
+        assert_ne!(
+            GenericArg::constant(interner, ConstValue(3)),
+            GenericArg::constant(interner, ConstValue(4))
+        );
+        assert_eq!(
+            Substitution::from_types(interner, [a(), b(a())]),
+            Substitution::from_types(interner, [a(), b(a())])
+        );
+        assert_ne!(
+            Substitution::from_types(interner, [a(), b(a())]),
+            Substitution::from_types(interner, [b(a()), a()])
+        );
+        assert_eq!(Substitution::empty(interner), Substitution::empty(interner));
+    }
+
+    #[test]
+    fn interner_can_be_shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<NaiveInterner>();
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn threads_intern_equal_types_to_one_handle() {
+        let interner = NaiveInterner::new();
+        let (A, B) = add_items!(interner, {
+            struct A;
+            struct B<T>;
+        });
+        let interner = &interner;
+        let new_types = || {
+            let a = Type::adt(interner, A, []);
+            let b_a = Type::adt(interner, B, [GenericArg::ty(interner, a.clone())]);
+            let pair = Type::tuple(interner, [a, b_a.clone()]);
+            [b_a, pair]
+        };
+        let from_threads: Vec<_> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8).map(|_| scope.spawn(new_types)).collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        let expected = new_types();
+        assert!(from_threads.iter().all(|types| *types == expected));
+    }
 }
@@ -1,6 +1,9 @@
 use interner::NaiveInterner;
-use solver_ir::add_items;
-use solver_macros::impl_patterns;
+use solver_ir::{
+    add_items,
+    solver::{Solver, TraitGoal},
+};
+use solver_macros::{add_impls, impl_patterns};
 
 mod interner;
 
@@ -12,14 +15,20 @@ fn main() {
         struct B<T>;
         trait Clone;
     });
-    let (matcher, matched) = impl_patterns!(use crate solver_ir, &interner, {
-        impl B<A> as Clone;
-        impl B<_> as Clone;
+    add_impls!(use crate solver_ir, &interner, {
+        impl A as Clone;
+        impl<T> B<T> as Clone where T: Clone;
     });
-    // let mut impl_repr = String::new();
-    // infer
-    //     .format_as_trait_impl(&interner, Clone, &mut impl_repr)
-    //     .unwrap();
-    // println!("{}", impl_repr);
-    println!("{}", matched.matches(&matcher));
+    let (goal,) = impl_patterns!(use crate solver_ir, &interner, {
+        impl B<B<A>> as Clone;
+    });
+    let goal = TraitGoal::new(&interner, Clone, &goal).unwrap();
+    let mut goal_repr = String::new();
+    goal.format(&interner, &mut goal_repr).unwrap();
+    let solver = Solver::new(&interner);
+    println!(
+        "`{}` is proven: {}",
+        goal_repr,
+        solver.solve(goal).is_proven()
+    );
 }